@@ -0,0 +1,88 @@
+use anyhow::{bail, Result};
+
+/// Replace the block between `begin` and `end` marker lines, or append `block`
+/// after a blank line if there is none
+///
+/// A `begin` marker without an `end` marker is an error rather than a reason
+/// to append a second block, as the file was edited by hand.
+pub fn replace(
+    existing: &str,
+    begin_marker: &str,
+    end_marker: &str,
+    block: &str,
+) -> Result<String> {
+    if let Some(start) = existing.find(begin_marker) {
+        let Some(end_offset) = existing[start..].find(end_marker) else {
+            bail!(
+                "Found '{}' without a matching '{}', fix or remove the block by hand",
+                begin_marker,
+                end_marker
+            );
+        };
+        let mut end = start + end_offset + end_marker.len();
+        if existing[end..].starts_with('\n') {
+            end += 1;
+        }
+        return Ok(format!(
+            "{}{}{}",
+            &existing[..start],
+            block,
+            &existing[end..]
+        ));
+    }
+
+    let mut out = existing.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(block);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGIN: &str = "# BEGIN generated";
+    const END: &str = "# END generated";
+
+    #[test]
+    fn test_replace_appends_then_replaces() {
+        let appended = replace(
+            "kept\n",
+            BEGIN,
+            END,
+            "# BEGIN generated\nold\n# END generated\n",
+        )
+        .unwrap();
+        assert_eq!(
+            appended,
+            "kept\n\n# BEGIN generated\nold\n# END generated\n"
+        );
+
+        let replaced = replace(
+            &appended,
+            BEGIN,
+            END,
+            "# BEGIN generated\nnew\n# END generated\n",
+        )
+        .unwrap();
+        assert_eq!(
+            replaced,
+            "kept\n\n# BEGIN generated\nnew\n# END generated\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_rejects_unterminated_block() {
+        let existing = "kept\n# BEGIN generated\nold\n";
+
+        let message = replace(existing, BEGIN, END, "# BEGIN generated\n# END generated\n")
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("without a matching '# END generated'"));
+    }
+}
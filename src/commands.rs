@@ -1,7 +1,21 @@
-use crate::config::Config;
-use crate::git;
-use crate::output::{self, OutputFormat, StatusOutput};
-use anyhow::Result;
+use crate::config::{self, Config, SigningConfig, SigningFormat, SshConfig};
+use crate::git::{self, RepoLocation};
+use crate::hooks::{self, Chain};
+use crate::includes;
+use crate::mailmap::{self, MailmapEntry};
+use crate::output::{
+    self, ApplyAllOutput, ApplyOutcome, ApplyResult, AuditClass, AuditIdentity, AuditOutput,
+    AuditSummary, MatchCandidate, MatchOutput, OutputFormat, RecursiveStatusOutput, RepoStatusRow,
+    StatusOutput,
+};
+use crate::scan;
+use crate::state::{Registry, RepoState};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// Create a new workspace configuration
 pub fn new_workspace(workspace: &str, name: &str, email: &str) -> Result<()> {
@@ -16,150 +30,1734 @@ pub fn new_workspace(workspace: &str, name: &str, email: &str) -> Result<()> {
     Ok(())
 }
 
+/// Changes requested by `figgit update`
+#[derive(Debug, Default)]
+pub struct WorkspaceUpdate {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub alias_emails: Vec<String>,
+    pub alias_names: Vec<String>,
+    pub committer_name: Option<String>,
+    pub committer_email: Option<String>,
+    pub clear_committer: bool,
+    pub patterns: Vec<String>,
+    pub paths: Vec<String>,
+    pub reset: bool,
+    pub priority: Option<i32>,
+    pub clear_priority: bool,
+    pub signing: SigningConfig,
+    pub clear_signing: bool,
+    pub ssh_key: Option<String>,
+    pub ssh_options: Vec<String>,
+    pub clear_ssh: bool,
+    pub git_set: Vec<(String, String)>,
+    pub git_unset: Vec<String>,
+}
+
+impl WorkspaceUpdate {
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.email.is_none()
+            && self.alias_emails.is_empty()
+            && self.alias_names.is_empty()
+            && self.committer_name.is_none()
+            && self.committer_email.is_none()
+            && !self.clear_committer
+            && self.patterns.is_empty()
+            && self.paths.is_empty()
+            && self.priority.is_none()
+            && !self.clear_priority
+            && self.signing == SigningConfig::default()
+            && !self.clear_signing
+            && self.ssh_key.is_none()
+            && self.ssh_options.is_empty()
+            && !self.clear_ssh
+            && self.git_set.is_empty()
+            && self.git_unset.is_empty()
+    }
+}
+
 /// Update an existing workspace configuration
+///
+/// When the settings it applies change, the registered repositories using it
+/// are listed and re-applied after confirmation, or directly with `yes`.
+/// `propagate` re-applies it even when nothing changed.
 pub fn update_workspace(
     workspace: &str,
-    name: Option<&str>,
-    email: Option<&str>,
-    patterns: Vec<String>,
-    reset: bool,
+    update: WorkspaceUpdate,
+    propagate: bool,
+    yes: bool,
 ) -> Result<()> {
-    if name.is_none() && email.is_none() && patterns.is_empty() {
+    if update.is_empty() && propagate {
+        return propagate_workspace(&Config::load()?, workspace, false);
+    }
+    if update.is_empty() {
         println!(
-            "No changes specified. Use --name, --email, and/or --pattern to update the workspace."
+            "No changes specified. Use --name, --email, --alias-email, --alias-name, --committer-name, --committer-email, --pattern, --path, --priority, --set, --unset, or the signing and SSH options to update the workspace."
         );
         return Ok(());
     }
 
-    let mut config = Config::load()?;
+    let mut config = Config::load()?;
+    let previous_settings = config.get_workspace(workspace)?.git_settings();
+
+    // Update name and email if provided
+    if update.name.is_some() || update.email.is_some() {
+        config.update_workspace(workspace, update.name.as_deref(), update.email.as_deref())?;
+    }
+
+    // Update aliases if provided
+    if !update.alias_emails.is_empty() || !update.alias_names.is_empty() {
+        config.update_workspace_aliases(
+            workspace,
+            update.alias_emails,
+            update.alias_names,
+            update.reset,
+        )?;
+    }
+
+    // Update the committer override if provided
+    if update.clear_committer {
+        config.clear_workspace_committer(workspace)?;
+    } else if update.committer_name.is_some() || update.committer_email.is_some() {
+        config.update_workspace_committer(
+            workspace,
+            update.committer_name,
+            update.committer_email,
+        )?;
+    }
+
+    // Update patterns if provided
+    if !update.patterns.is_empty() {
+        config.update_workspace_patterns(workspace, update.patterns, update.reset)?;
+    }
+
+    // Update path rules if provided
+    if !update.paths.is_empty() {
+        config.update_workspace_paths(workspace, update.paths, update.reset)?;
+    }
+
+    // Update priority if provided
+    if update.priority.is_some() || update.clear_priority {
+        config.update_workspace_priority(workspace, update.priority)?;
+    }
+
+    // Update signing settings if provided
+    if update.clear_signing {
+        config.clear_workspace_signing(workspace)?;
+    } else if update.signing != SigningConfig::default() {
+        config.update_workspace_signing(workspace, update.signing)?;
+    }
+
+    // Update SSH settings if provided
+    if update.clear_ssh {
+        config.clear_workspace_ssh(workspace)?;
+    } else if update.ssh_key.is_some() || !update.ssh_options.is_empty() {
+        config.update_workspace_ssh(workspace, update.ssh_key, update.ssh_options, update.reset)?;
+    }
+
+    // Update extra git config keys if provided
+    if !update.git_set.is_empty() || !update.git_unset.is_empty() {
+        config.update_workspace_git(workspace, update.git_set, update.git_unset)?;
+    }
+
+    config.save()?;
+
+    println!("✓ Updated workspace '{}'", workspace);
+    output::print_workspace_details(config.get_workspace(workspace)?, "  ");
+
+    let changed = config.get_workspace(workspace)?.git_settings() != previous_settings;
+    if propagate || changed {
+        println!();
+        propagate_workspace(&config, workspace, !propagate && !yes)?;
+    }
+
+    Ok(())
+}
+
+/// Re-apply a workspace to every repository it is registered for, forgetting
+/// the repositories that no longer exist
+///
+/// With `ask`, the repositories are listed and nothing is written unless the
+/// user confirms.
+fn propagate_workspace(config: &Config, workspace: &str, ask: bool) -> Result<()> {
+    let settings = config.get_workspace(workspace)?.git_settings();
+    let mut registry = Registry::load()?;
+    let repositories = registered_repositories(&registry, workspace);
+
+    if repositories.is_empty() {
+        if !ask {
+            println!("No repository is registered for workspace '{}'", workspace);
+        }
+        return Ok(());
+    }
+
+    if ask {
+        println!(
+            "{} repositories use workspace '{}':",
+            repositories.len(),
+            workspace
+        );
+        for repo in &repositories {
+            println!("  - {}", repo.display());
+        }
+        if !confirm("Re-apply the updated workspace to them?")? {
+            println!(
+                "Left unchanged. Run 'figgit update {} --propagate' to apply it later.",
+                workspace
+            );
+            return Ok(());
+        }
+    }
+
+    println!(
+        "Propagating workspace '{}' to {} repositories:",
+        workspace,
+        repositories.len()
+    );
+    let mut failures = 0;
+    for repo in repositories {
+        if !repo.exists() {
+            registry.remove(&repo);
+            println!("  - {} (no longer exists, removed)", repo.display());
+            continue;
+        }
+
+        let applied = git::discover_repo_at(&repo)
+            .and_then(|location| apply_settings_in(&location, workspace, &settings));
+        match applied {
+            Ok(()) => {
+                registry.record(&repo, workspace, SystemTime::now());
+                println!("  ✓ {}", repo.display());
+            }
+            Err(err) => {
+                failures += 1;
+                println!("  ✗ {}: {:#}", repo.display(), err);
+            }
+        }
+    }
+    registry.save()?;
+
+    if failures > 0 {
+        bail!("{} repositories could not be updated", failures);
+    }
+
+    Ok(())
+}
+
+/// Get the repositories a workspace is registered for
+fn registered_repositories(registry: &Registry, workspace: &str) -> Vec<PathBuf> {
+    registry
+        .repositories_of(workspace)
+        .into_iter()
+        .map(|(path, _)| PathBuf::from(path))
+        .collect()
+}
+
+/// Ask a yes/no question on the terminal; without a terminal the answer is no
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        println!("{} [y/N] no (not a terminal, use --yes)", question);
+        return Ok(false);
+    }
+
+    print!("{} [y/N] ", question);
+    io::stdout().flush().context("Failed to write to stdout")?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("Failed to read the answer")?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Apply a workspace configuration to the local git repository
+pub fn use_workspace(workspace: &str) -> Result<()> {
+    let config = Config::load()?;
+    let workspace_config = config.get_workspace(workspace)?;
+
+    let repo = git::discover_repo()?;
+    apply_settings_in(&repo, workspace, &workspace_config.git_settings())?;
+
+    let mut registry = Registry::load()?;
+    registry.record(repo.root(), workspace, SystemTime::now());
+    registry.save()?;
+
+    println!("✓ Applied workspace '{}' to local git config", workspace);
+    println!("  Name:  {}", workspace_config.name);
+    println!("  Email: {}", workspace_config.email);
+    if let Some(signing) = &workspace_config.signing {
+        println!("  Signing: {}", signing.summary());
+    }
+    if let Some(ssh_command) = workspace_config
+        .ssh
+        .as_ref()
+        .and_then(SshConfig::ssh_command)
+    {
+        println!("  SSH:   {}", ssh_command);
+    }
+    for (key, value) in &workspace_config.git {
+        println!("  {} = {}", key, value);
+    }
+
+    Ok(())
+}
+
+/// Write workspace settings to a repository
+fn apply_settings_in(
+    repo: &RepoLocation,
+    workspace: &str,
+    settings: &[(String, String)],
+) -> Result<()> {
+    let mut state = RepoState::load(repo)?.unwrap_or_default();
+    let is_set = |key: &str| settings.iter().any(|(setting, _)| setting == key);
+
+    let mut targets: Vec<(String, Vec<String>)> = settings
+        .iter()
+        .map(|(key, value)| (key.clone(), vec![value.clone()]))
+        .collect();
+    // Keys an earlier workspace applied and this one does not set go back to
+    // their value from before figgit, instead of carrying over. They stay
+    // recorded so that `figgit unuse` still restores them.
+    for (key, values) in state.stale_keys(settings) {
+        if !config::COMMITTER_KEYS.contains(&key.as_str()) {
+            targets.push((key.clone(), values.clone()));
+        }
+    }
+    // A committer left by another workspace would keep applying otherwise, so
+    // it is unset rather than restored
+    for key in config::COMMITTER_KEYS {
+        if !is_set(key) {
+            targets.push((key.to_string(), Vec::new()));
+        }
+    }
+
+    // One transaction, so that a failure leaves the repository as it was
+    let replaced: Vec<_> = git::set_local_values_in(repo, &targets)?
+        .into_iter()
+        .filter(|(key, values)| {
+            is_set(key) || (config::COMMITTER_KEYS.contains(&key.as_str()) && !values.is_empty())
+        })
+        .collect();
+
+    // Remember what was replaced so that `figgit unuse` can restore it
+    state.record(workspace, replaced);
+    state.save(repo)
+}
+
+/// Print shell exports of the author and committer identities of a workspace
+pub fn env_workspace(workspace: &str) -> Result<()> {
+    let config = Config::load()?;
+    let workspace_config = config.get_workspace(workspace)?;
+
+    for (key, value) in workspace_config.identity_env() {
+        println!("export {}={}", key, config::shell_quote(value));
+    }
+    Ok(())
+}
+
+/// Restore the local git config from before figgit applied a workspace, or
+/// unset every key figgit applied when `clear` is set
+pub fn unuse_workspace(clear: bool) -> Result<()> {
+    let repo = git::discover_repo()?;
+    let Some(state) = RepoState::load(&repo)? else {
+        bail!("No workspace was applied to this repository by figgit");
+    };
+
+    for (key, values) in &state.previous {
+        let values: &[String] = if clear { &[] } else { values };
+        git::restore_local_values_in(&repo, key, values)?;
+    }
+    RepoState::remove(&repo)?;
+
+    let mut registry = Registry::load()?;
+    if registry.remove(repo.root()) {
+        registry.save()?;
+    }
+
+    if clear {
+        println!(
+            "✓ Cleared the settings of workspace '{}' from local git config",
+            state.workspace
+        );
+    } else {
+        println!(
+            "✓ Restored local git config from before workspace '{}'",
+            state.workspace
+        );
+    }
+    for (key, values) in &state.previous {
+        if clear || values.is_empty() {
+            println!("  {} (unset)", key);
+        } else {
+            println!("  {} = {}", key, values.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+/// Detect the workspace from the repository remotes or location and apply it
+pub fn auto_workspace() -> Result<()> {
+    let config = Config::load()?;
+    let remotes = git::get_remote_urls()?;
+    let root = git::get_repo_root()?;
+
+    let matches = config.match_repository(&remotes, &root);
+
+    let Some(best) = config::select_best_match(&matches)? else {
+        let mut message =
+            String::from("No workspace pattern or path rule matches this repository:");
+        for (remote, url) in &remotes {
+            message.push_str(&format!("\n  remote {}: {}", remote, url));
+        }
+        message.push_str(&format!("\n  path: {}", root.display()));
+        bail!(message);
+    };
+
+    println!(
+        "Matched {} with '{}' (priority {}, specificity {})",
+        best.source, best.matched.pattern, best.matched.priority, best.matched.specificity
+    );
+
+    use_workspace(&best.matched.workspace)
+}
+
+/// Detect and apply the workspace of every repository under `root`
+pub fn apply_all(root: &Path, dry_run: bool, jobs: usize, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let root = git::resolve_path(root)?;
+    let root = root
+        .canonicalize()
+        .context(format!("Directory '{}' not found", root.display()))?;
+
+    let repositories = scan::find_repositories(&root);
+    let results = scan::parallel_map(&repositories, jobs, |repo| {
+        apply_detected_workspace(&config, repo, dry_run)
+    });
+
+    // Registered here rather than in the workers, which would race on the file
+    if !dry_run {
+        let mut registry = Registry::load()?;
+        let applied_at = SystemTime::now();
+        for result in &results {
+            if let (ApplyOutcome::Changed | ApplyOutcome::Unchanged, Some(workspace)) =
+                (result.outcome, &result.workspace)
+            {
+                registry.record(Path::new(&result.repository), workspace, applied_at);
+            }
+        }
+        registry.save()?;
+    }
+
+    let errors = results
+        .iter()
+        .filter(|result| result.outcome == ApplyOutcome::Error)
+        .count();
+    output::print_apply_all(
+        &ApplyAllOutput {
+            root: root.display().to_string(),
+            dry_run,
+            results,
+        },
+        format,
+    );
+
+    if errors > 0 {
+        bail!(
+            "{} of {} repositories could not be updated",
+            errors,
+            repositories.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Detect the workspace of one repository and apply it unless it is already set
+fn apply_detected_workspace(config: &Config, repo: &Path, dry_run: bool) -> ApplyResult {
+    let mut result = ApplyResult {
+        repository: repo.display().to_string(),
+        workspace: None,
+        outcome: ApplyOutcome::Error,
+        detail: None,
+    };
+
+    let outcome = (|| -> Result<ApplyOutcome> {
+        let repo = git::discover_repo_at(repo)?;
+        let remotes = git::get_remote_urls_in(&repo)?;
+        let matches = config.match_repository(&remotes, repo.root());
+
+        let Some(best) = config::select_best_match(&matches)? else {
+            result.detail = Some("no matching workspace".to_string());
+            return Ok(ApplyOutcome::Skipped);
+        };
+        let workspace = &best.matched.workspace;
+        result.workspace = Some(workspace.clone());
+
+        let settings = config.get_workspace(workspace)?.git_settings();
+        let snapshot = git::get_local_snapshot_in(&repo, &settings)?;
+        let unchanged = settings
+            .iter()
+            .zip(&snapshot)
+            .all(|((_, value), (_, current))| current == std::slice::from_ref(value));
+        if unchanged {
+            return Ok(ApplyOutcome::Unchanged);
+        }
+
+        if !dry_run {
+            apply_settings_in(&repo, workspace, &settings)?;
+        }
+        Ok(ApplyOutcome::Changed)
+    })();
+
+    match outcome {
+        Ok(outcome) => result.outcome = outcome,
+        Err(err) => result.detail = Some(format!("{:#}", err)),
+    }
+    result
+}
+
+/// Show every workspace pattern matching a URL, with its ranking score
+pub fn match_url(url: &str, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let effective_url = crate::url::rewrite(url, &git::get_url_rewrites()?);
+    let matches = config.match_remotes(&[(String::from("url"), effective_url.clone())]);
+
+    // A tie between workspaces means no candidate would be selected by 'auto'
+    let best = config::select_best_match(&matches).ok().flatten();
+
+    let match_output = MatchOutput {
+        url: url.to_string(),
+        canonical_url: crate::url::canonicalize(&effective_url),
+        candidates: matches
+            .iter()
+            .map(|m| MatchCandidate {
+                workspace: m.matched.workspace.clone(),
+                pattern: m.matched.pattern.clone(),
+                priority: m.matched.priority,
+                specificity: m.matched.specificity,
+                selected: best.is_some_and(|best| std::ptr::eq(best, m)),
+            })
+            .collect(),
+    };
+
+    output::print_match(&match_output, format);
+
+    Ok(())
+}
+
+/// Write one gitconfig fragment per workspace and manage `includeIf` entries
+/// for them in the global gitconfig
+pub fn install_includes(dry_run: bool) -> Result<()> {
+    let config = Config::load()?;
+    let entries = includes::include_entries(&config)?;
+    let block = includes::render_block(&entries);
+    let gitconfig_path = git::global_config_path()?;
+
+    let mut workspaces: Vec<_> = config.workspaces.iter().collect();
+    workspaces.sort_by_key(|(name, _)| *name);
+
+    // Names written before they were checked cannot become fragment files
+    workspaces.retain(|(name, _)| {
+        let usable = config::validate_workspace_name(name).is_ok();
+        if !usable {
+            println!(
+                "⚠ Skipping workspace '{}': its name cannot be used as a file name, so no fragment is generated for it",
+                name
+            );
+        }
+        usable
+    });
+
+    if dry_run {
+        for (name, workspace_config) in &workspaces {
+            println!("# {}", includes::fragment_path(name)?.display());
+            println!("{}", includes::render_fragment(name, workspace_config));
+        }
+        println!("# {}", gitconfig_path.display());
+        print!("{}", block);
+        return Ok(());
+    }
+
+    for (name, workspace_config) in &workspaces {
+        let path = includes::fragment_path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create config directory")?;
+        }
+        fs::write(&path, includes::render_fragment(name, workspace_config))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    // Remove fragments left behind by deleted workspaces
+    if let Ok(dir_entries) = fs::read_dir(includes::fragments_dir()?) {
+        for dir_entry in dir_entries.flatten() {
+            let path = dir_entry.path();
+            let is_stale = path.extension().is_some_and(|ext| ext == "gitconfig")
+                && path
+                    .file_stem()
+                    .is_some_and(|stem| !config.workspaces.contains_key(&*stem.to_string_lossy()));
+            if is_stale && includes::is_generated_fragment(&path) {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+    }
+
+    let existing = if gitconfig_path.exists() {
+        fs::read_to_string(&gitconfig_path)
+            .with_context(|| format!("Failed to read {}", gitconfig_path.display()))?
+    } else {
+        String::new()
+    };
+    let updated = includes::replace_block(&existing, &block)
+        .with_context(|| format!("Failed to update {}", gitconfig_path.display()))?;
+
+    if updated != existing {
+        fs::write(&gitconfig_path, updated)
+            .with_context(|| format!("Failed to write {}", gitconfig_path.display()))?;
+    }
+
+    println!(
+        "✓ Installed {} conditional include(s) for {} workspace(s) in {}",
+        entries.len(),
+        workspaces.len(),
+        gitconfig_path.display()
+    );
+    for (name, workspace_config) in &workspaces {
+        if workspace_config.patterns.is_empty() && workspace_config.paths.is_empty() {
+            println!(
+                "⚠ Workspace '{}' has no patterns or path rules and is never included",
+                name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// View workspace configurations
+pub fn view_workspace(workspace: Option<&str>, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+
+    match workspace {
+        Some(name) => {
+            // View a specific workspace
+            let workspace_config = config.get_workspace(name)?;
+            let registry = Registry::load()?;
+            let repositories = registry.repositories_of(name);
+
+            match format {
+                OutputFormat::Json => {
+                    let mut output = serde_json::json!({
+                        "name": name,
+                        "user_name": workspace_config.name,
+                        "email": workspace_config.email
+                    });
+                    if !workspace_config.alias_emails.is_empty() {
+                        output["alias_emails"] = serde_json::json!(workspace_config.alias_emails);
+                    }
+                    if !workspace_config.alias_names.is_empty() {
+                        output["alias_names"] = serde_json::json!(workspace_config.alias_names);
+                    }
+                    if !workspace_config.patterns.is_empty() {
+                        output["patterns"] = serde_json::json!(workspace_config.patterns);
+                    }
+                    if !workspace_config.paths.is_empty() {
+                        output["paths"] = serde_json::json!(workspace_config.paths);
+                    }
+                    if let Some(priority) = workspace_config.priority {
+                        output["priority"] = serde_json::json!(priority);
+                    }
+                    if let Some(signing) = &workspace_config.signing {
+                        output["signing"] = serde_json::json!(signing);
+                    }
+                    if let Some(ssh) = &workspace_config.ssh {
+                        output["ssh"] = serde_json::json!(ssh);
+                    }
+                    if let Some(committer) = &workspace_config.committer {
+                        output["committer"] = serde_json::json!(committer);
+                    }
+                    if !workspace_config.git.is_empty() {
+                        output["git"] = serde_json::json!(workspace_config.git);
+                    }
+                    if !repositories.is_empty() {
+                        output["repositories"] = repositories
+                            .iter()
+                            .map(|(path, entry)| {
+                                serde_json::json!({
+                                    "path": path,
+                                    "applied_at": entry.applied_at
+                                })
+                            })
+                            .collect();
+                    }
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                }
+                OutputFormat::Table => {
+                    let single = HashMap::from([(name.to_string(), workspace_config.clone())]);
+                    output::print_workspaces(&single, format);
+                    output::print_registered_repositories(&repositories, format);
+                }
+                OutputFormat::Default => {
+                    println!("Workspace: {}", name);
+                    output::print_workspace_details(workspace_config, "  ");
+                    output::print_registered_repositories(&repositories, format);
+                }
+            }
+        }
+        None => {
+            // View all workspaces
+            output::print_workspaces(&config.workspaces, format);
+        }
+    }
+
+    Ok(())
+}
+
+/// List all workspace configurations
+pub fn list_workspaces(format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    output::print_workspaces(&config.workspaces, format);
+    Ok(())
+}
+
+/// Show the current git configuration and compare with workspaces
+pub fn status(format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+
+    let repo = git::discover_repo().ok();
+    let mut status_output = StatusOutput {
+        repository: repo.as_ref().map(|repo| repo.root().display().to_string()),
+        current_name: None,
+        current_email: None,
+        current_committer_name: None,
+        current_committer_email: None,
+        current_signing: None,
+        current_ssh_command: None,
+        ssh_command_mismatch: false,
+        expected_ssh_command: None,
+        expected_committer: None,
+        matching_workspace: None,
+        match_kind: None,
+        available_workspaces: config.workspaces.keys().cloned().collect(),
+    };
+
+    status_output.available_workspaces.sort();
+
+    // Get current local git config
+    if let (Some(repo), Ok((name, email))) = (&repo, git::get_local_config()) {
+        status_output.current_name = Some(name.clone());
+        status_output.current_email = Some(email.clone());
+
+        status_output.current_committer_name = git::get_local_value_in(repo, "committer.name")?;
+        status_output.current_committer_email = git::get_local_value_in(repo, "committer.email")?;
+        status_output.current_signing = current_signing(repo)?;
+        status_output.current_ssh_command = git::get_config_value_in(repo, "core.sshCommand")?;
+        let local_ssh_command = git::get_local_value_in(repo, "core.sshCommand")?;
+
+        // Try to find a matching workspace, even if only the name or email matches
+        if let Some((workspace_name, workspace_config, kind)) =
+            config.find_identity_match(&name, &email)
+        {
+            status_output.matching_workspace = Some(workspace_name.clone());
+            status_output.match_kind = Some(kind);
+
+            let expected = workspace_config
+                .ssh
+                .as_ref()
+                .and_then(SshConfig::ssh_command);
+            // Git uses the effective command, wherever it is set. Where the
+            // workspace expects none, only a command left over in the local
+            // config is flagged, a global one is the user's own default
+            let mismatch = match &expected {
+                Some(_) => expected != status_output.current_ssh_command,
+                None => local_ssh_command.is_some(),
+            };
+            if mismatch {
+                status_output.ssh_command_mismatch = true;
+                status_output.expected_ssh_command = expected;
+            }
+
+            // Without committer.* git commits as the author
+            let committer_name = status_output.current_committer_name.as_deref();
+            let committer_email = status_output.current_committer_email.as_deref();
+            if !workspace_config.matches_committer(
+                committer_name.unwrap_or(&name),
+                committer_email.unwrap_or(&email),
+            ) {
+                status_output.expected_committer = Some(format!(
+                    "{} <{}>",
+                    workspace_config.committer_name(),
+                    workspace_config.committer_email()
+                ));
+            }
+        }
+    }
+
+    output::print_status(&status_output, format);
+
+    Ok(())
+}
+
+/// Report the identity and workspace of every repository under `root`
+pub fn recursive_status(root: &Path, jobs: usize, format: OutputFormat) -> Result<()> {
+    let config = Config::load()?;
+    let root = git::resolve_path(root)?;
+    let root = root
+        .canonicalize()
+        .context(format!("Directory '{}' not found", root.display()))?;
+
+    let repositories = scan::find_repositories(&root);
+    let rows = scan::parallel_map(&repositories, jobs, |repo| repo_status(&config, repo));
+
+    output::print_recursive_status(
+        &RecursiveStatusOutput {
+            root: root.display().to_string(),
+            repositories: rows,
+        },
+        format,
+    );
+
+    Ok(())
+}
+
+/// Compare the identity git uses in a repository with the workspace it should use
+fn repo_status(config: &Config, repo: &Path) -> RepoStatusRow {
+    let mut row = RepoStatusRow {
+        repository: repo.display().to_string(),
+        current_name: None,
+        current_email: None,
+        matching_workspace: None,
+        match_kind: None,
+        expected_workspace: None,
+        mismatch: false,
+        error: None,
+    };
+
+    let result = (|| -> Result<()> {
+        let repo = git::discover_repo_at(repo)?;
+
+        // Effective values, as commits in this repository would get them
+        row.current_name = git::get_config_value_in(&repo, "user.name")?;
+        row.current_email = git::get_config_value_in(&repo, "user.email")?;
+        if let (Some(name), Some(email)) = (&row.current_name, &row.current_email) {
+            if let Some((workspace, _, kind)) = config.find_identity_match(name, email) {
+                row.matching_workspace = Some(workspace.clone());
+                row.match_kind = Some(kind);
+            }
+        }
+
+        let remotes = git::get_remote_urls_in(&repo)?;
+        let matches = config.match_repository(&remotes, repo.root());
+        row.expected_workspace =
+            config::select_best_match(&matches)?.map(|best| best.matched.workspace.clone());
+
+        // A partial match means commits would still get part of another identity
+        row.mismatch = row.expected_workspace.is_some()
+            && (row.expected_workspace != row.matching_workspace
+                || row.match_kind.is_some_and(|kind| !kind.is_full()));
+        Ok(())
+    })();
+
+    if let Err(err) = result {
+        row.error = Some(format!("{:#}", err));
+    }
+    row
+}
+
+/// Verify that commits would be recorded with the identity of the workspace
+/// the repository remotes or location map to
+pub fn check(quiet: bool) -> Result<()> {
+    let config = Config::load()?;
+    let remotes = git::get_remote_urls()?;
+    let root = git::get_repo_root()?;
+
+    let matches = config.match_repository(&remotes, &root);
+    let Some(best) = config::select_best_match(&matches)? else {
+        if !quiet {
+            println!("No workspace pattern or path rule matches this repository, nothing to check");
+        }
+        return Ok(());
+    };
+    let workspace = &best.matched.workspace;
+    let workspace_config = config.get_workspace(workspace)?;
+
+    // Identities as git would record them, including GIT_AUTHOR_* and
+    // GIT_COMMITTER_* overrides
+    let author = git::get_ident("GIT_AUTHOR_IDENT")?;
+    let mut problems = Vec::new();
+    if !author
+        .as_ref()
+        .is_some_and(|(name, email)| workspace_config.matches_identity(name, email))
+    {
+        problems.push(format!(
+            "Identity {} does not match workspace '{}'\n  expected: {} <{}>",
+            describe_ident(&author),
+            workspace,
+            workspace_config.name,
+            workspace_config.email
+        ));
+    }
+    // A committer identical to the author was already checked above, unless the
+    // workspace expects another one
+    let committer = git::get_ident("GIT_COMMITTER_IDENT")?;
+    if (committer != author || workspace_config.committer.is_some())
+        && !committer
+            .as_ref()
+            .is_some_and(|(name, email)| workspace_config.matches_committer(name, email))
+    {
+        problems.push(format!(
+            "Committer {} does not match workspace '{}'\n  expected: {} <{}>",
+            describe_ident(&committer),
+            workspace,
+            workspace_config.committer_name(),
+            workspace_config.committer_email()
+        ));
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "{}\n  matched:  {} with '{}'\n\
+             Run 'figgit use {}' to fix this repository, or commit with --no-verify to skip this check",
+            problems.join("\n"),
+            best.source,
+            best.matched.pattern,
+            workspace
+        );
+    }
+
+    if let (Some((name, email)), false) = (&author, quiet) {
+        println!(
+            "✓ Identity {} <{}> matches workspace '{}'",
+            name, email, workspace
+        );
+    }
+    Ok(())
+}
+
+/// Format an identity read from git for messages
+fn describe_ident(ident: &Option<(String, String)>) -> String {
+    match ident {
+        Some((name, email)) => format!("{} <{}>", name, email),
+        None => "(none configured)".to_string(),
+    }
+}
+
+/// Verify that the commits a push sends are authored and committed with emails
+/// of the workspace owning the remote URL
+///
+/// The ref updates are read from stdin, as git passes them to the `pre-push` hook.
+pub fn check_pre_push(remote: Option<&str>, url: Option<&str>, quiet: bool) -> Result<()> {
+    let config = Config::load()?;
+    let remote = remote.unwrap_or("origin");
+    let url = match url {
+        Some(url) => url.to_string(),
+        None => git::get_config_value(&format!("remote.{}.url", remote))?
+            .context(format!("Remote '{}' not found", remote))?,
+    };
+    let url = crate::url::rewrite(&url, &git::get_url_rewrites()?);
+
+    let matches = config.match_remotes(&[(remote.to_string(), url.clone())]);
+    let Some(best) = config::select_best_match(&matches)? else {
+        if !quiet {
+            println!("No workspace pattern matches {}, nothing to check", url);
+        }
+        return Ok(());
+    };
+    let workspace = &best.matched.workspace;
+    let workspace_config = config.get_workspace(workspace)?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read the ref updates from stdin")?;
+    let updates = hooks::parse_ref_updates(&input)?;
+
+    // Commits already on the remote were checked when they were pushed
+    let mut revisions: Vec<String> = updates
+        .iter()
+        .filter(|update| !update.is_deletion())
+        .map(|update| update.local_oid.clone())
+        .collect();
+    if revisions.is_empty() {
+        return Ok(());
+    }
+    revisions.push("--not".to_string());
+    revisions.push(format!("--remotes={}", remote));
+    revisions.extend(
+        updates
+            .iter()
+            .filter(|update| !update.is_new() && git::commit_exists(&update.remote_oid))
+            .map(|update| update.remote_oid.clone()),
+    );
+    let commits = git::get_commits(&revisions)?;
+
+    let offending: Vec<String> = commits
+        .iter()
+        .filter_map(|commit| {
+            let mut problems = Vec::new();
+            if !workspace_config.owns_email(&commit.author_email) {
+                problems.push(format!(
+                    "author {} <{}>",
+                    commit.author_name, commit.author_email
+                ));
+            }
+            if !workspace_config.owns_committer_email(&commit.committer_email) {
+                problems.push(format!(
+                    "committer {} <{}>",
+                    commit.committer_name, commit.committer_email
+                ));
+            }
+            (!problems.is_empty()).then(|| {
+                format!(
+                    "  {} {}\n      {}",
+                    commit.short_id,
+                    commit.subject,
+                    problems.join(", ")
+                )
+            })
+        })
+        .collect();
+
+    if !offending.is_empty() {
+        bail!(
+            "{} of {} outgoing commits do not belong to workspace '{}' <{}>, matched by '{}':\n\
+             {}\n\
+             Rewrite them with 'figgit fix-authors --workspace {} <range>', \
+             or push with --no-verify to skip this check",
+            offending.len(),
+            commits.len(),
+            workspace,
+            workspace_config.email,
+            best.matched.pattern,
+            offending.join("\n"),
+            workspace
+        );
+    }
+
+    if !quiet {
+        println!(
+            "✓ {} outgoing commits belong to workspace '{}'",
+            commits.len(),
+            workspace
+        );
+    }
+    Ok(())
+}
+
+/// Classify the author and committer identities of a commit range against the
+/// workspace the repository expects
+///
+/// Without `--workspace`, the expected workspace is the one the remotes or path
+/// rules select. Fails when an identity leaks from another workspace, or with
+/// `strict` when one belongs to no workspace.
+pub fn audit(
+    range: Option<&str>,
+    workspace: Option<&str>,
+    strict: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let config = Config::load()?;
+    let root = git::get_repo_root()?;
+    let expected = expected_workspace(&config, &root, workspace)?;
+
+    let range = range.unwrap_or("HEAD");
+    let commits = git::get_commits(&[range.to_string()])?;
+    let identities = audit_identities(&config, &expected, &commits);
+
+    let count = |class: AuditClass| {
+        identities
+            .iter()
+            .filter(|identity| identity.class == class)
+            .count()
+    };
+    let summary = AuditSummary {
+        commits: commits.len(),
+        identities: identities.len(),
+        matching: count(AuditClass::Match),
+        leaks: count(AuditClass::Leak),
+        unknown: count(AuditClass::Unknown),
+    };
+    let (leaks, unknown) = (summary.leaks, summary.unknown);
+
+    output::print_audit(
+        &AuditOutput {
+            repository: root.display().to_string(),
+            range: range.to_string(),
+            expected_workspace: expected.clone(),
+            identities,
+            summary,
+        },
+        format,
+    );
+
+    if leaks > 0 {
+        bail!(
+            "{} identities from other workspaces found in commits of '{}'",
+            leaks,
+            expected
+        );
+    }
+    if strict && unknown > 0 {
+        bail!("{} identities belong to no workspace", unknown);
+    }
+
+    Ok(())
+}
+
+/// Get the workspace a repository should use: the given one, or the one its
+/// remotes or path rules select
+fn expected_workspace(config: &Config, root: &Path, workspace: Option<&str>) -> Result<String> {
+    if let Some(workspace) = workspace {
+        config.get_workspace(workspace)?;
+        return Ok(workspace.to_string());
+    }
+
+    let remotes = git::get_remote_urls()?;
+    let matches = config.match_repository(&remotes, root);
+    let Some(best) = config::select_best_match(&matches)? else {
+        bail!(
+            "No workspace pattern or path rule matches this repository, \
+             pass the expected one with --workspace"
+        );
+    };
+    Ok(best.matched.workspace.clone())
+}
+
+/// Group the authors and committers of `commits` by identity and classify them,
+/// leaks first, then unknown identities, then the most used ones
+fn audit_identities(
+    config: &Config,
+    expected: &str,
+    commits: &[git::CommitInfo],
+) -> Vec<AuditIdentity> {
+    let expected_config = config.workspaces.get(expected);
+    let mut identities: Vec<AuditIdentity> = Vec::new();
+
+    // Commits are listed newest first, so the first one seen is the last commit
+    for commit in commits {
+        let roles = [
+            (&commit.author_name, &commit.author_email, true),
+            (&commit.committer_name, &commit.committer_email, false),
+        ];
+        for (name, email, authored) in roles {
+            let index = match identities
+                .iter()
+                .position(|identity| &identity.name == name && &identity.email == email)
+            {
+                Some(index) => index,
+                None => {
+                    identities.push(AuditIdentity {
+                        name: name.clone(),
+                        email: email.clone(),
+                        class: AuditClass::Unknown,
+                        workspace: None,
+                        authored: 0,
+                        committed: 0,
+                        last_commit: commit.short_id.clone(),
+                    });
+                    identities.len() - 1
+                }
+            };
+            if authored {
+                identities[index].authored += 1;
+            } else {
+                identities[index].committed += 1;
+            }
+        }
+    }
+
+    for identity in &mut identities {
+        // The committer override only accounts for commits it committed
+        let committer_only = identity.authored == 0;
+        if expected_config.is_some_and(|config| {
+            config.owns_email(&identity.email)
+                || (committer_only && config.owns_committer_email(&identity.email))
+        }) {
+            identity.class = AuditClass::Match;
+            identity.workspace = Some(expected.to_string());
+        } else if let Some(owner) = config
+            .workspaces
+            .iter()
+            .filter(|(_, config)| config.owns_email(&identity.email))
+            .map(|(workspace, _)| workspace)
+            .min()
+        {
+            identity.class = AuditClass::Leak;
+            identity.workspace = Some(owner.clone());
+        }
+    }
+
+    identities.sort_by_key(|identity| {
+        (
+            identity.class,
+            std::cmp::Reverse(identity.authored + identity.committed),
+        )
+    });
+    identities
+}
+
+/// Rewrite the author and committer of the commits in `range` to the workspace
+/// identity, re-signing them when the workspace signs commits
+///
+/// The range end must be HEAD or a local branch, which is moved to the
+/// rewritten commits. Commits reachable from a remote-tracking branch are only
+/// rewritten with `force`.
+pub fn fix_authors(workspace: &str, range: &str, force: bool) -> Result<()> {
+    let config = Config::load()?;
+    let workspace_config = config.get_workspace(workspace)?;
+
+    let Some((base, tip)) = range
+        .split_once("..")
+        .filter(|(_, tip)| !tip.starts_with('.'))
+    else {
+        bail!("Expected a range like origin/main..HEAD, got '{}'", range);
+    };
+    let tip = if tip.is_empty() { "HEAD" } else { tip };
+    let base_id = git::resolve_commit(if base.is_empty() { "HEAD" } else { base })?;
+    let tip_id = git::resolve_commit(tip)?;
+
+    // The ref moved to the rewritten commits: a local branch, or HEAD itself
+    let branch = format!("refs/heads/{}", tip);
+    let reference = if git::ref_exists(&branch) {
+        branch
+    } else if tip_id == git::resolve_commit("HEAD")? {
+        "HEAD".to_string()
+    } else {
+        bail!(
+            "'{}' is neither HEAD nor a local branch, there is no ref to update",
+            tip
+        );
+    };
+
+    let commits = git::rev_list(&[
+        "--reverse".to_string(),
+        "--topo-order".to_string(),
+        tip_id.clone(),
+        "--not".to_string(),
+        base_id.clone(),
+    ])?;
+    if commits.is_empty() {
+        println!("No commits in {}, nothing to fix", range);
+        return Ok(());
+    }
+
+    let unpushed = git::rev_list(&[
+        tip_id.clone(),
+        "--not".to_string(),
+        base_id,
+        "--remotes".to_string(),
+    ])?;
+    let published = commits.len() - unpushed.len();
+    if published > 0 && !force {
+        bail!(
+            "{} of the {} commits in {} are on a remote-tracking branch, \
+             rewriting them would diverge from the remote; use --force to rewrite them anyway",
+            published,
+            commits.len(),
+            range
+        );
+    }
+
+    let signing = workspace_config.signing.as_ref();
+    let new = git::NewCommit {
+        name: &workspace_config.name,
+        email: &workspace_config.email,
+        committer_name: workspace_config.committer_name(),
+        committer_email: workspace_config.committer_email(),
+        settings: &signing.map(SigningConfig::git_settings).unwrap_or_default(),
+        sign: signing.is_some_and(SigningConfig::signs_commits),
+    };
+
+    let mut rewritten: HashMap<String, String> = HashMap::new();
+    for id in &commits {
+        let commit = git::get_raw_commit(id)?;
+        let parents: Vec<String> = commit
+            .parents
+            .iter()
+            .map(|parent| rewritten.get(parent).unwrap_or(parent).clone())
+            .collect();
+        let new_id = git::create_commit(&commit, &parents, &new)?;
+        rewritten.insert(id.clone(), new_id);
+    }
+
+    let new_tip = &rewritten[&tip_id];
+    git::update_ref(
+        &reference,
+        new_tip,
+        Some(&tip_id),
+        &format!("figgit fix-authors: rewrite {} as '{}'", range, workspace),
+    )?;
+    // Like rebase, keep the previous tip at hand to undo the rewrite
+    git::update_ref("ORIG_HEAD", &tip_id, None, "figgit fix-authors")?;
+
+    println!(
+        "✓ Rewrote {} commits as {} <{}>{}",
+        commits.len(),
+        workspace_config.name,
+        workspace_config.email,
+        if new.sign { ", signed" } else { "" }
+    );
+    for id in commits.iter().rev() {
+        println!("  {} -> {}", id, rewritten[id]);
+    }
+    println!(
+        "Updated {}, the previous tip is saved as ORIG_HEAD",
+        reference
+    );
+
+    Ok(())
+}
+
+/// Map the identities of every workspace found in the history onto the identity
+/// of the workspace the repository expects
+///
+/// Prints the managed `.mailmap` block, merges it into the repository `.mailmap`
+/// with `write`, or with `check` fails when some of these identities are not
+/// mapped by the current mailmap at all.
+pub fn mailmap(workspace: Option<&str>, write: bool, check: bool) -> Result<()> {
+    let config = Config::load()?;
+    let root = git::get_repo_root()?;
+    let expected = expected_workspace(&config, &root, workspace)?;
+    let expected_config = config.get_workspace(&expected)?;
+
+    let mut identities: Vec<(String, String)> = git::get_commits(&["HEAD".to_string()])?
+        .into_iter()
+        .flat_map(|commit| {
+            [
+                (commit.author_name, commit.author_email),
+                (commit.committer_name, commit.committer_email),
+            ]
+        })
+        .collect();
+    identities.sort();
+    identities.dedup();
+
+    if check {
+        let canonical = (expected_config.name.clone(), expected_config.email.clone());
+        let known: Vec<(String, String)> = identities
+            .into_iter()
+            .filter(|(_, email)| {
+                config
+                    .workspaces
+                    .values()
+                    .any(|workspace| workspace.owns_email(email))
+            })
+            .collect();
+        let mapped = git::check_mailmap(&known)?;
+
+        // Identities a hand-written entry maps elsewhere count as mapped
+        let unmapped: Vec<String> = known
+            .iter()
+            .zip(&mapped)
+            .filter(|(identity, mapped)| identity == mapped && **identity != canonical)
+            .map(|((name, email), _)| format!("  {} <{}>", name, email))
+            .collect();
+        if !unmapped.is_empty() {
+            bail!(
+                "{} workspace identities in the history are not mapped by the mailmap:\n{}\n\
+                 Run 'figgit mailmap --write' to map them onto {} <{}>",
+                unmapped.len(),
+                unmapped.join("\n"),
+                canonical.0,
+                canonical.1
+            );
+        }
+
+        println!("✓ Every workspace identity in the history is mapped");
+        return Ok(());
+    }
+
+    let mailmap_path = root.join(".mailmap");
+    let existing = fs::read_to_string(&mailmap_path).unwrap_or_default();
+    let entries = mailmap_entries(
+        &config,
+        &expected,
+        &identities,
+        &mailmap::hand_written_emails(&existing),
+    );
+    let block = mailmap::render_block(&entries);
+
+    if !write {
+        print!("{}", block);
+        return Ok(());
+    }
+
+    let updated = mailmap::replace_block(&existing, &block)
+        .with_context(|| format!("Failed to update {}", mailmap_path.display()))?;
+    if updated == existing {
+        println!("✓ {} is up to date", mailmap_path.display());
+        return Ok(());
+    }
+    fs::write(&mailmap_path, updated).context("Failed to write .mailmap")?;
+
+    println!(
+        "✓ Wrote {} entries mapping onto workspace '{}' to {}",
+        entries.len(),
+        expected,
+        mailmap_path.display()
+    );
+    Ok(())
+}
+
+/// Build the mailmap entries for the workspace identities among `identities`
+///
+/// Emails of other workspaces are mapped onto the expected identity, and so are
+/// other names used with its email. Only identities found in the history are
+/// mapped, so that a repository never discloses the emails of unrelated
+/// workspaces, and emails with a hand-written entry are left to it.
+fn mailmap_entries(
+    config: &Config,
+    expected: &str,
+    identities: &[(String, String)],
+    hand_written: &[String],
+) -> Vec<MailmapEntry> {
+    let Some(expected_config) = config.workspaces.get(expected) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<MailmapEntry> = identities
+        .iter()
+        .filter(|(_, email)| !hand_written.contains(&email.to_lowercase()))
+        .filter_map(|(name, email)| {
+            if expected_config.owns_email(email) {
+                let renamed = *name != expected_config.name || *email != expected_config.email;
+                renamed.then(|| MailmapEntry {
+                    proper_name: expected_config.name.clone(),
+                    proper_email: expected_config.email.clone(),
+                    commit_email: (*email != expected_config.email).then(|| email.clone()),
+                })
+            } else {
+                let known = config
+                    .workspaces
+                    .values()
+                    .any(|workspace| workspace.owns_email(email));
+                known.then(|| MailmapEntry {
+                    proper_name: expected_config.name.clone(),
+                    proper_email: expected_config.email.clone(),
+                    commit_email: Some(email.clone()),
+                })
+            }
+        })
+        .collect();
+
+    entries.sort();
+    entries.dedup();
+    entries
+}
 
-    // Update name and email if provided
-    if name.is_some() || email.is_some() {
-        config.update_workspace(workspace, name, email)?;
-    }
+/// Install the figgit pre-commit and pre-push hooks in the current repository,
+/// or for every repository through the global `core.hooksPath`
+pub fn hook_install(global: bool) -> Result<()> {
+    let figgit = std::env::current_exe().context("Unable to locate the figgit executable")?;
 
-    // Update patterns if provided
-    if !patterns.is_empty() {
-        config.update_workspace_patterns(workspace, patterns, reset)?;
+    if global {
+        return install_global_hooks(&figgit);
     }
 
-    config.save()?;
+    let hooks_dir = git::get_hooks_dir()?;
+    if hooks_dir == hooks::global_hooks_dir()? {
+        bail!("The global figgit hooks are installed, this repository already runs 'figgit check'");
+    }
+    fs::create_dir_all(&hooks_dir).context("Failed to create hooks directory")?;
 
-    println!("✓ Updated workspace '{}'", workspace);
-    let updated = config.get_workspace(workspace)?;
-    println!("  Name:     {}", updated.name);
-    println!("  Email:    {}", updated.email);
-    if !updated.patterns.is_empty() {
-        println!("  Patterns:");
-        for pattern in &updated.patterns {
-            println!("    - {}", pattern);
+    for (name, check) in hooks::CHECK_HOOKS {
+        let hook_path = hooks_dir.join(name);
+        let backup_path = hooks_dir.join(format!("{}{}", name, hooks::BACKUP_SUFFIX));
+        if hook_path.exists() {
+            let content = fs::read_to_string(&hook_path).unwrap_or_default();
+            if !hooks::is_figgit_hook(&content) {
+                if backup_path.exists() {
+                    bail!(
+                        "Both {} and {} exist, move one of them away first",
+                        hook_path.display(),
+                        backup_path.display()
+                    );
+                }
+                fs::rename(&hook_path, &backup_path)
+                    .context("Failed to back up the existing hook")?;
+                println!(
+                    "Existing {} hook moved to {}, it still runs after the check",
+                    name,
+                    backup_path.display()
+                );
+            }
         }
+
+        let script = hooks::render_hook(name, &figgit, Some(check), &Chain::File(&backup_path));
+        write_hook(&hook_path, &script)?;
+
+        println!("✓ Installed {} hook in {}", name, hook_path.display());
+    }
+    Ok(())
+}
+
+/// Point the global `core.hooksPath` at figgit hooks forwarding to the previous ones
+fn install_global_hooks(figgit: &Path) -> Result<()> {
+    let hooks_dir = hooks::global_hooks_dir()?;
+    let previous_file = hooks_dir.join(hooks::PREVIOUS_HOOKS_PATH_FILE);
+
+    let current = git::get_global_value("core.hooksPath")?;
+    let installed = current
+        .as_deref()
+        .is_some_and(|path| Path::new(path) == hooks_dir);
+
+    // Reinstalling keeps the hooks path recorded by the first install
+    let previous = if installed {
+        fs::read_to_string(&previous_file)
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+    } else {
+        current
+    };
+
+    fs::create_dir_all(&hooks_dir).context("Failed to create hooks directory")?;
+    fs::write(&previous_file, previous.as_deref().unwrap_or(""))
+        .context("Failed to record the previous hooks path")?;
+
+    let previous_dir = previous.as_deref().map(expand_home);
+    for name in hooks::CLIENT_HOOKS {
+        let previous_hook = previous_dir.as_ref().map(|dir| dir.join(name));
+        let chain = match &previous_hook {
+            Some(path) => Chain::File(path),
+            None => Chain::RepositoryHook,
+        };
+        write_hook(
+            &hooks_dir.join(name),
+            &hooks::render_hook(name, figgit, hooks::check_args(name), &chain),
+        )?;
     }
 
+    git::set_global_value("core.hooksPath", &hooks_dir.display().to_string())?;
+
+    println!("✓ Installed global hooks in {}", hooks_dir.display());
+    match &previous {
+        Some(path) => println!(
+            "  Hooks from the previous core.hooksPath ({}) still run",
+            path
+        ),
+        None => println!("  Repository hooks still run after the figgit ones"),
+    }
     Ok(())
 }
 
-/// Apply a workspace configuration to the local git repository
-pub fn use_workspace(workspace: &str) -> Result<()> {
-    let config = Config::load()?;
-    let workspace_config = config.get_workspace(workspace)?;
+/// Remove the figgit hooks and restore the hooks they replaced
+pub fn hook_uninstall(global: bool) -> Result<()> {
+    if global {
+        return uninstall_global_hooks();
+    }
 
-    git::set_local_config(&workspace_config.name, &workspace_config.email)?;
+    let hooks_dir = git::get_hooks_dir()?;
+    if hooks_dir == hooks::global_hooks_dir()? {
+        bail!("This repository uses the global figgit hooks, run 'figgit hook uninstall --global'");
+    }
 
-    println!("✓ Applied workspace '{}' to local git config", workspace);
-    println!("  Name:  {}", workspace_config.name);
-    println!("  Email: {}", workspace_config.email);
+    let mut removed = false;
+    for (name, _) in hooks::CHECK_HOOKS {
+        let hook_path = hooks_dir.join(name);
+        let backup_path = hooks_dir.join(format!("{}{}", name, hooks::BACKUP_SUFFIX));
+        let content = fs::read_to_string(&hook_path).unwrap_or_default();
+        if !hooks::is_figgit_hook(&content) {
+            continue;
+        }
+
+        fs::remove_file(&hook_path).context(format!("Failed to remove the {} hook", name))?;
+        if backup_path.exists() {
+            fs::rename(&backup_path, &hook_path).context("Failed to restore the previous hook")?;
+            println!("✓ Restored the previous {} hook", name);
+        }
+        println!("✓ Removed {} hook from {}", name, hook_path.display());
+        removed = true;
+    }
 
+    if !removed {
+        bail!("The figgit hooks are not installed in this repository");
+    }
     Ok(())
 }
 
-/// View workspace configurations
-pub fn view_workspace(workspace: Option<&str>, format: OutputFormat) -> Result<()> {
-    let config = Config::load()?;
+/// Restore the global `core.hooksPath` and remove the figgit hooks
+fn uninstall_global_hooks() -> Result<()> {
+    let hooks_dir = hooks::global_hooks_dir()?;
+    let previous_file = hooks_dir.join(hooks::PREVIOUS_HOOKS_PATH_FILE);
 
-    match workspace {
-        Some(name) => {
-            // View a specific workspace
-            let workspace_config = config.get_workspace(name)?;
+    let current = git::get_global_value("core.hooksPath")?;
+    if current
+        .as_deref()
+        .is_none_or(|path| Path::new(path) != hooks_dir)
+    {
+        bail!("The global figgit hooks are not installed");
+    }
 
-            match format {
-                OutputFormat::Json => {
-                    let mut output = serde_json::json!({
-                        "name": name,
-                        "user_name": workspace_config.name,
-                        "email": workspace_config.email
-                    });
-                    if !workspace_config.patterns.is_empty() {
-                        output["patterns"] = serde_json::json!(workspace_config.patterns);
-                    }
-                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
-                }
-                _ => {
-                    println!("Workspace: {}", name);
-                    println!("  Name:  {}", workspace_config.name);
-                    println!("  Email: {}", workspace_config.email);
-                    if !workspace_config.patterns.is_empty() {
-                        println!("  Patterns:");
-                        for pattern in &workspace_config.patterns {
-                            println!("    - {}", pattern);
-                        }
-                    }
-                }
-            }
+    let previous = fs::read_to_string(&previous_file)
+        .ok()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    match &previous {
+        Some(path) => {
+            git::set_global_value("core.hooksPath", path)?;
+            println!("✓ Restored core.hooksPath to {}", path);
         }
         None => {
-            // View all workspaces
-            output::print_workspaces(&config.workspaces, format);
+            git::unset_global_value("core.hooksPath")?;
+            println!("✓ Unset core.hooksPath");
+        }
+    }
+
+    // Only figgit's own files are removed, anything added by hand stays
+    for name in hooks::CLIENT_HOOKS {
+        let hook_path = hooks_dir.join(name);
+        let content = fs::read_to_string(&hook_path).unwrap_or_default();
+        if hooks::is_figgit_hook(&content) {
+            fs::remove_file(&hook_path).context("Failed to remove hook")?;
         }
     }
+    fs::remove_file(&previous_file).ok();
+    fs::remove_dir(&hooks_dir).ok();
 
+    println!("✓ Removed global hooks from {}", hooks_dir.display());
     Ok(())
 }
 
-/// List all workspace configurations
-pub fn list_workspaces(format: OutputFormat) -> Result<()> {
-    let config = Config::load()?;
-    output::print_workspaces(&config.workspaces, format);
+/// Write an executable hook script
+fn write_hook(path: &Path, script: &str) -> Result<()> {
+    fs::write(path, script).context(format!("Failed to write hook {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .context(format!("Failed to make hook {} executable", path.display()))?;
+    }
+
     Ok(())
 }
 
-/// Show the current git configuration and compare with workspaces
-pub fn status(format: OutputFormat) -> Result<()> {
-    let config = Config::load()?;
+/// Expand a leading `~/` the way git does for `core.hooksPath`
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
 
-    let mut status_output = StatusOutput {
-        current_name: None,
-        current_email: None,
-        matching_workspace: None,
-        available_workspaces: config.workspaces.keys().cloned().collect(),
+/// Read the signing settings of the local config of a repository
+fn current_signing(repo: &RepoLocation) -> Result<Option<SigningConfig>> {
+    let parse_bool = |value: Option<String>| {
+        value.map(|v| matches!(v.to_lowercase().as_str(), "true" | "yes" | "on" | "1"))
     };
 
-    status_output.available_workspaces.sort();
-
-    // Get current local git config
-    if let Ok((name, email)) = git::get_local_config() {
-        status_output.current_name = Some(name.clone());
-        status_output.current_email = Some(email.clone());
+    let signing = SigningConfig {
+        format: git::get_local_value_in(repo, "gpg.format")?
+            .as_deref()
+            .and_then(SigningFormat::from_str),
+        key: git::get_local_value_in(repo, "user.signingkey")?,
+        sign_commits: parse_bool(git::get_local_value_in(repo, "commit.gpgsign")?),
+        sign_tags: parse_bool(git::get_local_value_in(repo, "tag.gpgsign")?),
+    };
 
-        // Try to find a matching workspace
-        if let Some((workspace_name, _)) = config.find_matching_workspace(&name, &email) {
-            status_output.matching_workspace = Some(workspace_name.clone());
-        }
+    if signing == SigningConfig::default() {
+        Ok(None)
+    } else {
+        Ok(Some(signing))
     }
-
-    output::print_status(&status_output, format);
-
-    Ok(())
 }
 
 /// Delete a workspace configuration
-pub fn delete_workspace(workspace: &str) -> Result<()> {
+pub fn delete_workspace(workspace: &str, yes: bool) -> Result<()> {
     let mut config = Config::load()?;
+    let settings = config.get_workspace(workspace)?.git_settings();
+    let mut registry = Registry::load()?;
+    let repositories = registered_repositories(&registry, workspace);
+
+    // Ask before deleting anything, so that an interrupted prompt changes nothing
+    let mut unset = false;
+    if !repositories.is_empty() {
+        println!(
+            "{} repositories use workspace '{}':",
+            repositories.len(),
+            workspace
+        );
+        for repo in &repositories {
+            println!("  - {}", repo.display());
+        }
+        unset = yes || confirm("Restore the git config they had before figgit?")?;
+        println!();
+    }
+
     config.delete_workspace(workspace)?;
     config.save()?;
 
     println!("✓ Deleted workspace '{}'", workspace);
 
+    if repositories.is_empty() {
+        return Ok(());
+    }
+
+    // Repositories left as they are stay registered, since they still hold the
+    // settings of the deleted workspace
+    if !unset {
+        println!("Their local git config still holds the settings of the deleted workspace.");
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for repo in &repositories {
+        if !repo.exists() {
+            registry.remove(repo);
+            println!("  - {} (no longer exists, removed)", repo.display());
+            continue;
+        }
+
+        let restored = git::discover_repo_at(repo)
+            .and_then(|location| restore_applied_settings(&location, &settings));
+        match restored {
+            Ok(()) => {
+                registry.remove(repo);
+                println!("  ✓ {} (restored)", repo.display());
+            }
+            Err(err) => {
+                failures += 1;
+                println!("  ✗ {}: {:#}", repo.display(), err);
+            }
+        }
+    }
+    registry.save()?;
+
+    if failures > 0 {
+        bail!("{} repositories could not be updated", failures);
+    }
+
+    Ok(())
+}
+
+/// Give every key figgit applied to a repository back its value from before
+/// figgit, like `figgit unuse`, and unset the given workspace settings it has
+/// no record of
+fn restore_applied_settings(repo: &RepoLocation, settings: &[(String, String)]) -> Result<()> {
+    let state = RepoState::load(repo)?;
+
+    for (key, _) in settings {
+        let recorded = state.as_ref().is_some_and(|state| {
+            state
+                .previous
+                .keys()
+                .any(|recorded| recorded.eq_ignore_ascii_case(key))
+        });
+        if !recorded {
+            git::restore_local_values_in(repo, key, &[])?;
+        }
+    }
+    if let Some(state) = &state {
+        for (key, values) in &state.previous {
+            git::restore_local_values_in(repo, key, values)?;
+        }
+        RepoState::remove(repo)?;
+    }
+
     Ok(())
 }
 
@@ -191,14 +1789,319 @@ pub fn import_workspace(workspace: &str, global: bool, from: Option<&str>) -> Re
     Ok(())
 }
 
+/// Import one workspace per identity included through `includeIf` sections of
+/// the global git config
+pub fn import_from_includes() -> Result<()> {
+    let mut config = Config::load()?;
+    let gitconfig_path = git::global_config_path()?;
+    let config_dir = gitconfig_path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+
+    println!(
+        "Importing from includeIf sections of {}",
+        gitconfig_path.display()
+    );
+
+    // Group conditions by the identity they include, keeping file order
+    let mut identities: Vec<((String, String), String, Vec<includes::ImportedRule>)> = Vec::new();
+
+    for (condition, path) in git::get_conditional_includes(&gitconfig_path)? {
+        let include_path = includes::resolve_include_path(&path, &config_dir);
+
+        if includes::is_generated_fragment(&include_path) {
+            continue;
+        }
+
+        let Some(rule) = includes::rule_from_condition(&condition, &config_dir) else {
+            if condition.starts_with("gitdir/i:") {
+                println!(
+                    "⚠ Skipping case-insensitive condition '{}': path rules are case-sensitive, add it with 'figgit update --path' in the case your repositories use",
+                    condition
+                );
+            } else {
+                println!("⚠ Skipping unsupported condition '{}'", condition);
+            }
+            continue;
+        };
+
+        // Keys missing from the included file are inherited from the global config
+        let read = |key: &str| {
+            git::get_value_from_file(&include_path, key)
+                .or_else(|_| git::get_value_from_file(&gitconfig_path, key))
+        };
+        let identity = match read("user.name").and_then(|name| Ok((name, read("user.email")?))) {
+            Ok(identity) => identity,
+            Err(err) => {
+                println!("⚠ Skipping '{}': {}", condition, err);
+                continue;
+            }
+        };
+
+        match identities
+            .iter_mut()
+            .find(|(existing, _, _)| *existing == identity)
+        {
+            Some((_, _, rules)) => rules.push(rule),
+            None => {
+                let workspace = includes::workspace_name_from_path(&include_path)
+                    .unwrap_or_else(|| identity.1.clone());
+                identities.push((identity, workspace, vec![rule]));
+            }
+        }
+    }
+
+    if identities.is_empty() {
+        println!("No conditional includes with a user identity found.");
+        return Ok(());
+    }
+
+    let mut imported = 0;
+    for ((name, email), workspace, rules) in identities {
+        if let Err(err) = config.add_workspace(&workspace, &name, &email) {
+            println!("⚠ Skipping '{}': {}", workspace, err);
+            continue;
+        }
+
+        let mut patterns = Vec::new();
+        let mut paths = Vec::new();
+        for rule in rules {
+            match rule {
+                includes::ImportedRule::Pattern(pattern) => patterns.push(pattern),
+                includes::ImportedRule::Path(path) => paths.push(path),
+            }
+        }
+        config.update_workspace_patterns(&workspace, patterns, false)?;
+        config.update_workspace_paths(&workspace, paths, false)?;
+
+        let created = config.get_workspace(&workspace)?;
+        println!("✓ Imported workspace '{}'", workspace);
+        println!("  Name:  {}", created.name);
+        println!("  Email: {}", created.email);
+        for pattern in &created.patterns {
+            println!("  Pattern: {}", pattern);
+        }
+        for path in &created.paths {
+            println!("  Path:    {}", path);
+        }
+        imported += 1;
+    }
+
+    if imported > 0 {
+        config.save()?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempRepo;
 
     // Note: These tests are simplified and would need more sophisticated
     // setup/teardown in a real testing environment to avoid conflicts
     // with the actual user config.
 
+    #[test]
+    fn test_switching_workspace_removes_its_ssh_settings() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_ssh(
+                "work",
+                Some("~/.ssh/id_work".to_string()),
+                Vec::new(),
+                false,
+            )
+            .unwrap();
+        config
+            .add_workspace("personal", "John Doe", "john@personal.com")
+            .unwrap();
+        let work = config.get_workspace("work").unwrap().git_settings();
+        let personal = config.get_workspace("personal").unwrap().git_settings();
+
+        let repo = TempRepo::new("switch");
+        apply_settings_in(&repo, "work", &work).unwrap();
+        let applied = git::get_local_value_in(&repo, "core.sshCommand").unwrap();
+        apply_settings_in(&repo, "personal", &personal).unwrap();
+        let left = git::get_local_value_in(&repo, "core.sshCommand").unwrap();
+        let email = git::get_local_value_in(&repo, "user.email").unwrap();
+        let state = RepoState::load(&repo).unwrap().unwrap();
+
+        assert!(applied.is_some_and(|command| command.contains("~/.ssh/id_work")));
+        assert_eq!(left, None);
+        assert_eq!(email.as_deref(), Some("john@personal.com"));
+        // Still recorded, so that `unuse` restores the value from before figgit
+        assert_eq!(state.workspace, "personal");
+        assert!(state.previous["core.sshCommand"].is_empty());
+    }
+
+    #[test]
+    fn test_switching_workspace_removes_its_signing_settings() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_signing(
+                "work",
+                SigningConfig {
+                    format: Some(SigningFormat::Ssh),
+                    key: Some("~/.ssh/id_work.pub".to_string()),
+                    sign_commits: Some(true),
+                    sign_tags: Some(true),
+                },
+            )
+            .unwrap();
+        config
+            .add_workspace("personal", "John Doe", "john@personal.com")
+            .unwrap();
+        let work = config.get_workspace("work").unwrap().git_settings();
+        let personal = config.get_workspace("personal").unwrap().git_settings();
+
+        let repo = TempRepo::new("signing");
+        apply_settings_in(&repo, "work", &work).unwrap();
+        let applied = current_signing(&repo).unwrap();
+        apply_settings_in(&repo, "personal", &personal).unwrap();
+        let left = current_signing(&repo).unwrap();
+
+        assert_eq!(applied, config.get_workspace("work").unwrap().signing);
+        assert_eq!(left, None);
+    }
+
+    #[test]
+    fn test_switching_workspace_restores_its_git_keys() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_git(
+                "work",
+                vec![("pull.rebase".to_string(), "true".to_string())],
+                Vec::new(),
+            )
+            .unwrap();
+        config
+            .add_workspace("personal", "John Doe", "john@personal.com")
+            .unwrap();
+        let work = config.get_workspace("work").unwrap().git_settings();
+        let personal = config.get_workspace("personal").unwrap().git_settings();
+
+        let repo = TempRepo::new("git-keys");
+        // Set before figgit, so switching away from 'work' brings it back
+        git::set_local_value_in(&repo, "pull.rebase", "false").unwrap();
+        apply_settings_in(&repo, "work", &work).unwrap();
+        let applied = git::get_local_value_in(&repo, "pull.rebase").unwrap();
+        apply_settings_in(&repo, "personal", &personal).unwrap();
+        let restored = git::get_local_value_in(&repo, "pull.rebase").unwrap();
+
+        assert_eq!(applied.as_deref(), Some("true"));
+        assert_eq!(restored.as_deref(), Some("false"));
+    }
+
+    #[test]
+    fn test_switching_workspace_unsets_its_committer() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_committer("work", None, Some("ci@work.com".to_string()))
+            .unwrap();
+        config
+            .update_workspace_git(
+                "work",
+                vec![("pull.rebase".to_string(), "true".to_string())],
+                Vec::new(),
+            )
+            .unwrap();
+        config
+            .add_workspace("personal", "John Doe", "john@personal.com")
+            .unwrap();
+        let work = config.get_workspace("work").unwrap().git_settings();
+        let personal = config.get_workspace("personal").unwrap().git_settings();
+
+        let repo = TempRepo::new("committer");
+        apply_settings_in(&repo, "work", &work).unwrap();
+        let applied = git::get_local_value_in(&repo, "committer.email").unwrap();
+        apply_settings_in(&repo, "personal", &personal).unwrap();
+
+        assert_eq!(applied.as_deref(), Some("ci@work.com"));
+        assert_eq!(
+            git::get_local_value_in(&repo, "committer.email").unwrap(),
+            None
+        );
+        assert_eq!(git::get_local_value_in(&repo, "pull.rebase").unwrap(), None);
+        let state = RepoState::load(&repo).unwrap().unwrap();
+        assert!(state.previous["committer.email"].is_empty());
+    }
+
+    #[test]
+    fn test_propagating_cleared_ssh_settings_removes_them() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_ssh(
+                "work",
+                Some("~/.ssh/id_work".to_string()),
+                Vec::new(),
+                false,
+            )
+            .unwrap();
+        config
+            .update_workspace_git(
+                "work",
+                vec![("pull.rebase".to_string(), "true".to_string())],
+                Vec::new(),
+            )
+            .unwrap();
+        let before = config.get_workspace("work").unwrap().git_settings();
+        config.clear_workspace_ssh("work").unwrap();
+        config
+            .update_workspace_git("work", Vec::new(), vec!["pull.rebase".to_string()])
+            .unwrap();
+        let after = config.get_workspace("work").unwrap().git_settings();
+
+        let repo = TempRepo::new("propagate");
+        apply_settings_in(&repo, "work", &before).unwrap();
+        // What `update --clear-ssh --unset pull.rebase --yes` re-applies
+        apply_settings_in(&repo, "work", &after).unwrap();
+
+        assert_eq!(
+            git::get_local_value_in(&repo, "core.sshCommand").unwrap(),
+            None
+        );
+        assert_eq!(git::get_local_value_in(&repo, "pull.rebase").unwrap(), None);
+    }
+
+    #[test]
+    fn test_restore_applied_settings_keeps_original_values() {
+        let repo = TempRepo::new("restore");
+        let settings = vec![
+            ("user.name".to_string(), "John Doe".to_string()),
+            ("user.email".to_string(), "john@work.com".to_string()),
+        ];
+        git::set_local_value_in(&repo, "user.email", "me@example.com").unwrap();
+        apply_settings_in(&repo, "work", &settings).unwrap();
+        restore_applied_settings(&repo, &settings).unwrap();
+
+        assert_eq!(git::get_local_value_in(&repo, "user.name").unwrap(), None);
+        assert_eq!(
+            git::get_local_value_in(&repo, "user.email")
+                .unwrap()
+                .as_deref(),
+            Some("me@example.com")
+        );
+        assert_eq!(RepoState::load(&repo).unwrap(), None);
+    }
+
     #[test]
     fn test_new_workspace_creates_config() {
         // This is a basic test structure
@@ -243,8 +2146,7 @@ mod tests {
         // This is environment-dependent
         let result = git::get_global_config();
 
-        if result.is_ok() {
-            let (name, email) = result.unwrap();
+        if let Ok((name, email)) = result {
             assert!(!name.is_empty(), "Global user.name should not be empty");
             assert!(!email.is_empty(), "Global user.email should not be empty");
 
@@ -265,8 +2167,7 @@ mod tests {
         if git::is_git_repo() {
             let result = git::get_local_config();
 
-            if result.is_ok() {
-                let (name, email) = result.unwrap();
+            if let Ok((name, email)) = result {
                 assert!(!name.is_empty(), "Local user.name should not be empty");
                 assert!(!email.is_empty(), "Local user.email should not be empty");
 
@@ -328,4 +2229,104 @@ mod tests {
         assert!(config.get_workspace("work").is_ok());
         assert!(config.get_workspace("personal").is_ok());
     }
+
+    #[test]
+    fn test_audit_identities() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "Jane Doe", "jane@company.com")
+            .unwrap();
+        config
+            .add_workspace("personal", "Jane Doe", "jane@home.org")
+            .unwrap();
+
+        let commit = |id: &str, author: (&str, &str), committer: (&str, &str)| git::CommitInfo {
+            id: id.to_string(),
+            short_id: id.to_string(),
+            author_name: author.0.to_string(),
+            author_email: author.1.to_string(),
+            committer_name: committer.0.to_string(),
+            committer_email: committer.1.to_string(),
+            subject: String::new(),
+        };
+        let jane = ("Jane Doe", "jane@company.com");
+        let commits = vec![
+            commit("c3", jane, ("GitHub", "noreply@github.com")),
+            commit("c2", ("Jane Doe", "jane@home.org"), jane),
+            commit("c1", jane, jane),
+        ];
+
+        let identities = audit_identities(&config, "work", &commits);
+        let summary: Vec<_> = identities
+            .iter()
+            .map(|identity| {
+                (
+                    identity.email.as_str(),
+                    identity.class,
+                    identity.workspace.as_deref(),
+                    identity.authored,
+                    identity.committed,
+                    identity.last_commit.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "jane@home.org",
+                    AuditClass::Leak,
+                    Some("personal"),
+                    1,
+                    0,
+                    "c2"
+                ),
+                ("noreply@github.com", AuditClass::Unknown, None, 0, 1, "c3"),
+                (
+                    "jane@company.com",
+                    AuditClass::Match,
+                    Some("work"),
+                    2,
+                    2,
+                    "c3"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mailmap_entries() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "Jane Doe", "jane@company.com")
+            .unwrap();
+        config
+            .add_workspace("personal", "Jane", "jane@home.org")
+            .unwrap();
+        config
+            .add_workspace("oss", "jdoe", "jdoe@users.noreply.github.com")
+            .unwrap();
+
+        let identity = |name: &str, email: &str| (name.to_string(), email.to_string());
+        let identities = vec![
+            identity("Bob", "bob@company.com"),
+            identity("J. Doe", "jane@company.com"),
+            identity("Jane Doe", "jane@company.com"),
+            identity("Jane", "jane@home.org"),
+            identity("jdoe", "jdoe@users.noreply.github.com"),
+        ];
+        let hand_written = vec!["jdoe@users.noreply.github.com".to_string()];
+
+        let rendered: Vec<String> = mailmap_entries(&config, "work", &identities, &hand_written)
+            .iter()
+            .map(MailmapEntry::render)
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "Jane Doe <jane@company.com>",
+                "Jane Doe <jane@company.com> <jane@home.org>",
+            ]
+        );
+    }
 }
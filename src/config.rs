@@ -1,15 +1,466 @@
+use crate::identity::{self, IdentityMatch};
+use crate::pattern;
 use anyhow::{bail, Context, Result};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct WorkspaceConfig {
     pub name: String,
     pub email: String,
+    /// Other emails commits of this workspace are made with, never written by `use`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alias_emails: Vec<String>,
+    /// Other spellings of the name commits of this workspace are made with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alias_names: Vec<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub patterns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshConfig>,
+    /// Committer identity, when commits should not be committed as the author
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub committer: Option<CommitterConfig>,
+    /// Extra git config keys, e.g. `"pull.rebase" = "true"`
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_git_table"
+    )]
+    pub git: BTreeMap<String, String>,
+}
+
+/// Keys of the committer override, unset when applying a workspace without one
+pub const COMMITTER_KEYS: &[&str] = &["committer.name", "committer.email"];
+
+/// Keys written from dedicated workspace settings, which the `git` table may not override
+const MANAGED_KEYS: &[&str] = &[
+    "user.name",
+    "user.email",
+    "committer.name",
+    "committer.email",
+    "user.signingkey",
+    "gpg.format",
+    "commit.gpgsign",
+    "tag.gpgsign",
+    "core.sshcommand",
+];
+
+/// Check whether a key is written from a dedicated workspace setting
+fn is_managed_key(key: &str) -> bool {
+    MANAGED_KEYS.contains(&key.to_lowercase().as_str())
+}
+
+/// Read the `git` table, accepting both quoted keys (`"pull.rebase" = true`) and
+/// nested tables (`[workspaces.work.git.pull]`), with any scalar value
+fn deserialize_git_table<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    fn flatten(
+        prefix: &str,
+        value: toml::Value,
+        settings: &mut BTreeMap<String, String>,
+    ) -> Result<(), String> {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    let key = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    flatten(&key, value, settings)?;
+                }
+            }
+            toml::Value::String(value) => {
+                settings.insert(prefix.to_string(), value);
+            }
+            toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                settings.insert(prefix.to_string(), value.to_string());
+            }
+            _ => return Err(format!("unsupported value for git key '{}'", prefix)),
+        }
+        Ok(())
+    }
+
+    let mut settings = BTreeMap::new();
+    flatten("", toml::Value::deserialize(deserializer)?, &mut settings)
+        .map_err(serde::de::Error::custom)?;
+    Ok(settings)
+}
+
+/// Check that a workspace name can be used as a file name, as fragments are
+/// named after their workspace
+pub fn validate_workspace_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        bail!(
+            "Invalid workspace name '{}': it may not be empty, start with '.', or contain '/' or '\\'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Check that a key has git's `section[.subsection].name` form
+pub fn is_valid_git_key(key: &str) -> bool {
+    let (Some((section, _)), Some((_, name))) = (key.split_once('.'), key.rsplit_once('.')) else {
+        return false;
+    };
+
+    !section.is_empty()
+        && section
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// SSH identity used for the remotes of a workspace, applied via `core.sshCommand`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SshConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    /// Extra `-o` options, e.g. `StrictHostKeyChecking=accept-new`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+impl SshConfig {
+    /// The `core.sshCommand` value for these settings, if there is anything to set
+    ///
+    /// An identity file implies `IdentitiesOnly=yes`, so that ssh does not offer
+    /// the keys of another account loaded in the agent first.
+    pub fn ssh_command(&self) -> Option<String> {
+        if self.identity_file.is_none() && self.options.is_empty() {
+            return None;
+        }
+
+        let mut command = String::from("ssh");
+        if let Some(identity_file) = &self.identity_file {
+            command.push_str(&format!(" -i {}", shell_quote(identity_file)));
+            command.push_str(" -o IdentitiesOnly=yes");
+        }
+        for option in &self.options {
+            command.push_str(&format!(" -o {}", shell_quote(option)));
+        }
+        Some(command)
+    }
+}
+
+/// Quote a word for a POSIX shell, like the one git runs `core.sshCommand` with
+///
+/// Plain words are left alone so that a leading `~` is still expanded.
+pub fn shell_quote(word: &str) -> String {
+    let is_plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "~/._-=:@+,".contains(c));
+    if is_plain {
+        return word.to_string();
+    }
+
+    match word.strip_prefix("~/") {
+        Some(rest) => format!("~/'{}'", rest.replace('\'', "'\\''")),
+        None => format!("'{}'", word.replace('\'', "'\\''")),
+    }
+}
+
+/// Committer name and email overriding the workspace identity for the committer
+///
+/// Either field may be set alone; the other one stays the workspace one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CommitterConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// Commit and tag signing settings of a workspace
+///
+/// Every field is optional; only the ones that are set are written to git.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SigningConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<SigningFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign_commits: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign_tags: Option<bool>,
+}
+
+/// Signature format, as accepted by git's `gpg.format`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    Openpgp,
+    Ssh,
+    X509,
+}
+
+impl SigningFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "openpgp" | "gpg" => Some(Self::Openpgp),
+            "ssh" => Some(Self::Ssh),
+            "x509" => Some(Self::X509),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Openpgp => "openpgp",
+            Self::Ssh => "ssh",
+            Self::X509 => "x509",
+        }
+    }
+}
+
+impl fmt::Display for SigningFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl SigningConfig {
+    /// Git config keys and values for these signing settings
+    pub fn git_settings(&self) -> Vec<(String, String)> {
+        let mut settings = Vec::new();
+        if let Some(format) = self.format {
+            settings.push(("gpg.format".to_string(), format.to_string()));
+        }
+        if let Some(key) = &self.key {
+            settings.push(("user.signingkey".to_string(), key.clone()));
+        }
+        if let Some(sign_commits) = self.sign_commits {
+            settings.push(("commit.gpgsign".to_string(), sign_commits.to_string()));
+        }
+        if let Some(sign_tags) = self.sign_tags {
+            settings.push(("tag.gpgsign".to_string(), sign_tags.to_string()));
+        }
+        settings
+    }
+
+    /// Check whether these settings turn commit signing on, as setting a key
+    /// alone does not
+    pub fn signs_commits(&self) -> bool {
+        self.sign_commits.unwrap_or(false)
+    }
+
+    /// One-line summary, e.g. `ssh, key ~/.ssh/id_work.pub, commits, tags`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(format) = self.format {
+            parts.push(format.to_string());
+        }
+        if let Some(key) = &self.key {
+            parts.push(format!("key {}", key));
+        }
+        match self.sign_commits {
+            Some(true) => parts.push("commits".to_string()),
+            Some(false) => parts.push("no commits".to_string()),
+            None => {}
+        }
+        match self.sign_tags {
+            Some(true) => parts.push("tags".to_string()),
+            Some(false) => parts.push("no tags".to_string()),
+            None => {}
+        }
+        parts.join(", ")
+    }
+}
+
+impl WorkspaceConfig {
+    /// Git config keys and values that make up this workspace identity
+    pub fn git_settings(&self) -> Vec<(String, String)> {
+        let mut settings = vec![
+            ("user.name".to_string(), self.name.clone()),
+            ("user.email".to_string(), self.email.clone()),
+        ];
+        // git reads committer.* before user.* for the committer since 2.22
+        if let Some(committer) = &self.committer {
+            if let Some(name) = &committer.name {
+                settings.push(("committer.name".to_string(), name.clone()));
+            }
+            if let Some(email) = &committer.email {
+                settings.push(("committer.email".to_string(), email.clone()));
+            }
+        }
+        if let Some(signing) = &self.signing {
+            settings.extend(signing.git_settings());
+        }
+        if let Some(ssh_command) = self.ssh.as_ref().and_then(SshConfig::ssh_command) {
+            settings.push(("core.sshCommand".to_string(), ssh_command));
+        }
+        settings.extend(
+            self.git
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        settings
+    }
+
+    /// Name commits of this workspace are committed with
+    pub fn committer_name(&self) -> &str {
+        self.committer
+            .as_ref()
+            .and_then(|committer| committer.name.as_deref())
+            .unwrap_or(&self.name)
+    }
+
+    /// Email commits of this workspace are committed with
+    pub fn committer_email(&self) -> &str {
+        self.committer
+            .as_ref()
+            .and_then(|committer| committer.email.as_deref())
+            .unwrap_or(&self.email)
+    }
+
+    /// `GIT_AUTHOR_*` and `GIT_COMMITTER_*` variables making git use this identity
+    pub fn identity_env(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("GIT_AUTHOR_NAME", &self.name),
+            ("GIT_AUTHOR_EMAIL", &self.email),
+            ("GIT_COMMITTER_NAME", self.committer_name()),
+            ("GIT_COMMITTER_EMAIL", self.committer_email()),
+        ]
+    }
+
+    /// Describe the committer override as `name <email>`, if any
+    pub fn committer_summary(&self) -> Option<String> {
+        self.committer
+            .as_ref()
+            .map(|_| format!("{} <{}>", self.committer_name(), self.committer_email()))
+    }
+
+    /// Check whether a committer name and email are the ones of the workspace,
+    /// once normalized
+    ///
+    /// Without an override the committer is expected to be the workspace identity.
+    pub fn matches_committer(&self, name: &str, email: &str) -> bool {
+        if self.committer.is_none() {
+            return self.matches_identity(name, email);
+        }
+        identity::normalize_name(self.committer_name()) == identity::normalize_name(name)
+            && identity::normalize_email(self.committer_email()) == identity::normalize_email(email)
+    }
+
+    /// Check whether a committer email is the committer override or belongs to
+    /// the workspace
+    pub fn owns_committer_email(&self, email: &str) -> bool {
+        let override_email = self
+            .committer
+            .as_ref()
+            .and_then(|committer| committer.email.as_deref());
+        override_email.is_some_and(|known| {
+            identity::normalize_email(known) == identity::normalize_email(email)
+        }) || self.owns_email(email)
+    }
+
+    /// Check whether commits with this name and email belong to the workspace,
+    /// accepting its alias names and emails and normalized spellings of them
+    pub fn matches_identity(&self, name: &str, email: &str) -> bool {
+        self.match_identity(name, email)
+            .is_some_and(|kind| kind.is_full())
+    }
+
+    /// Classify how a name and email match the workspace identity or its aliases
+    pub fn match_identity(&self, name: &str, email: &str) -> Option<IdentityMatch> {
+        let exact_name = self.names().any(|known| known == name);
+        let exact_email = self.emails().any(|known| known == email);
+        if exact_name && exact_email {
+            return Some(IdentityMatch::Exact);
+        }
+
+        match (self.owns_name(name), self.owns_email(email)) {
+            (true, true) => Some(IdentityMatch::Normalized),
+            (false, true) => Some(IdentityMatch::EmailOnly),
+            (true, false) => Some(IdentityMatch::NameOnly),
+            (false, false) => None,
+        }
+    }
+
+    /// Check whether a name is the workspace name or one of its alias names,
+    /// once normalized
+    pub fn owns_name(&self, name: &str) -> bool {
+        let name = identity::normalize_name(name);
+        self.names()
+            .any(|known| identity::normalize_name(known) == name)
+    }
+
+    /// Check whether an author or committer email is the workspace email or one
+    /// of its alias emails, once normalized
+    pub fn owns_email(&self, email: &str) -> bool {
+        let email = identity::normalize_email(email);
+        self.emails()
+            .any(|known| identity::normalize_email(known) == email)
+    }
+
+    fn names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.name).chain(&self.alias_names)
+    }
+
+    fn emails(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.email).chain(&self.alias_emails)
+    }
+}
+
+/// A workspace URL pattern or path rule that matched, with the data used to rank it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    pub workspace: String,
+    pub pattern: String,
+    pub priority: i32,
+    pub specificity: usize,
+}
+
+impl PatternMatch {
+    /// Ranking key: explicit priority first, then the most specific pattern
+    pub fn score(&self) -> (i32, usize) {
+        (self.priority, self.specificity)
+    }
+}
+
+/// What a workspace rule was matched against
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchSource {
+    /// One of the repository remotes
+    Remote { remote: String, url: String },
+    /// The location of the repository on disk
+    Path(PathBuf),
+}
+
+impl fmt::Display for MatchSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchSource::Remote { remote, url } => write!(f, "remote '{}' ({})", remote, url),
+            MatchSource::Path(path) => write!(f, "path {}", path.display()),
+        }
+    }
+}
+
+/// A rule match against a repository remote or location
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub source: MatchSource,
+    pub matched: PatternMatch,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -35,11 +486,37 @@ impl Config {
 
         let content = fs::read_to_string(&config_path).context("Failed to read config file")?;
 
-        let config: Config = toml::from_str(&content).context("Failed to parse config file")?;
+        let mut config: Config = toml::from_str(&content).context("Failed to parse config file")?;
+        for (name, key) in config.remove_managed_git_keys() {
+            // Warnings go to stderr so JSON output stays parseable
+            eprintln!(
+                "⚠ Ignoring git key '{}' of workspace '{}': it is managed by figgit, remove it from the git table and use the dedicated setting instead",
+                key, name
+            );
+        }
 
         Ok(config)
     }
 
+    /// Remove the `git` table keys that a dedicated setting writes, which the
+    /// file format cannot prevent, and return them as (workspace, key) pairs
+    fn remove_managed_git_keys(&mut self) -> Vec<(String, String)> {
+        let mut removed = Vec::new();
+
+        for (name, workspace) in &mut self.workspaces {
+            workspace.git.retain(|key, _| {
+                let managed = is_managed_key(key);
+                if managed {
+                    removed.push((name.clone(), key.clone()));
+                }
+                !managed
+            });
+        }
+
+        removed.sort();
+        removed
+    }
+
     /// Save the configuration to the config file
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
@@ -58,6 +535,7 @@ impl Config {
 
     /// Add a new workspace
     pub fn add_workspace(&mut self, name: &str, user_name: &str, email: &str) -> Result<()> {
+        validate_workspace_name(name)?;
         if self.workspaces.contains_key(name) {
             bail!(
                 "Workspace '{}' already exists. Use 'update' to modify it.",
@@ -70,7 +548,7 @@ impl Config {
             WorkspaceConfig {
                 name: user_name.to_string(),
                 email: email.to_string(),
-                patterns: Vec::new(),
+                ..Default::default()
             },
         );
 
@@ -127,127 +605,572 @@ impl Config {
         Ok(())
     }
 
-    /// Get a workspace by name
-    pub fn get_workspace(&self, name: &str) -> Result<&WorkspaceConfig> {
-        self.workspaces
-            .get(name)
-            .context(format!("Workspace '{}' not found", name))
-    }
+    /// Update workspace directory path rules
+    pub fn update_workspace_paths(
+        &mut self,
+        name: &str,
+        paths: Vec<String>,
+        reset: bool,
+    ) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
 
-    /// Delete a workspace
-    pub fn delete_workspace(&mut self, name: &str) -> Result<()> {
-        if self.workspaces.remove(name).is_none() {
-            bail!("Workspace '{}' not found", name);
+        if reset {
+            workspace.paths = paths;
+        } else {
+            for path in paths {
+                if !workspace.paths.contains(&path) {
+                    workspace.paths.push(path);
+                }
+            }
         }
+
         Ok(())
     }
 
-    /// Find a workspace that matches the given name and email
-    pub fn find_matching_workspace(
-        &self,
+    /// Update workspace alias emails and names
+    pub fn update_workspace_aliases(
+        &mut self,
         name: &str,
-        email: &str,
-    ) -> Option<(&String, &WorkspaceConfig)> {
-        self.workspaces
-            .iter()
-            .find(|(_, config)| config.name == name && config.email == email)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+        emails: Vec<String>,
+        names: Vec<String>,
+        reset: bool,
+    ) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
 
-    #[test]
-    fn test_add_workspace() {
-        let mut config = Config::default();
-        config
-            .add_workspace("work", "John Doe", "john@work.com")
-            .unwrap();
+        for (aliases, given) in [
+            (&mut workspace.alias_emails, emails),
+            (&mut workspace.alias_names, names),
+        ] {
+            if given.is_empty() {
+                continue;
+            }
+            if reset {
+                aliases.clear();
+            }
+            for alias in given {
+                if !aliases.contains(&alias) {
+                    aliases.push(alias);
+                }
+            }
+        }
 
-        assert_eq!(config.workspaces.len(), 1);
-        let workspace = config.get_workspace("work").unwrap();
-        assert_eq!(workspace.name, "John Doe");
-        assert_eq!(workspace.email, "john@work.com");
+        Ok(())
     }
 
-    #[test]
-    fn test_add_duplicate_workspace() {
-        let mut config = Config::default();
-        config
-            .add_workspace("work", "John Doe", "john@work.com")
-            .unwrap();
+    /// Set or clear the explicit priority of a workspace
+    pub fn update_workspace_priority(&mut self, name: &str, priority: Option<i32>) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
 
-        let result = config.add_workspace("work", "Jane Doe", "jane@work.com");
-        assert!(result.is_err());
-    }
+        workspace.priority = priority;
 
-    #[test]
-    fn test_update_workspace() {
-        let mut config = Config::default();
-        config
-            .add_workspace("work", "John Doe", "john@work.com")
-            .unwrap();
+        Ok(())
+    }
 
-        config
-            .update_workspace("work", Some("Jane Doe"), None)
-            .unwrap();
-        let workspace = config.get_workspace("work").unwrap();
-        assert_eq!(workspace.name, "Jane Doe");
-        assert_eq!(workspace.email, "john@work.com");
+    /// Merge signing settings into a workspace, keeping the fields left unset
+    pub fn update_workspace_signing(&mut self, name: &str, changes: SigningConfig) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
 
-        config
-            .update_workspace("work", None, Some("jane@work.com"))
-            .unwrap();
-        let workspace = config.get_workspace("work").unwrap();
-        assert_eq!(workspace.name, "Jane Doe");
-        assert_eq!(workspace.email, "jane@work.com");
-    }
+        let signing = workspace.signing.get_or_insert_with(SigningConfig::default);
+        if changes.format.is_some() {
+            signing.format = changes.format;
+        }
+        if changes.key.is_some() {
+            signing.key = changes.key;
+        }
+        if changes.sign_commits.is_some() {
+            signing.sign_commits = changes.sign_commits;
+        }
+        if changes.sign_tags.is_some() {
+            signing.sign_tags = changes.sign_tags;
+        }
 
-    #[test]
-    fn test_update_nonexistent_workspace() {
-        let mut config = Config::default();
-        let result = config.update_workspace("work", Some("John Doe"), None);
-        assert!(result.is_err());
+        Ok(())
     }
 
-    #[test]
-    fn test_delete_workspace() {
-        let mut config = Config::default();
-        config
-            .add_workspace("work", "John Doe", "john@work.com")
-            .unwrap();
+    /// Remove the signing settings of a workspace
+    pub fn clear_workspace_signing(&mut self, name: &str) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
 
-        config.delete_workspace("work").unwrap();
-        assert_eq!(config.workspaces.len(), 0);
-    }
+        workspace.signing = None;
 
-    #[test]
-    fn test_delete_nonexistent_workspace() {
-        let mut config = Config::default();
-        let result = config.delete_workspace("work");
-        assert!(result.is_err());
+        Ok(())
     }
 
-    #[test]
-    fn test_find_matching_workspace() {
-        let mut config = Config::default();
-        config
-            .add_workspace("work", "John Doe", "john@work.com")
-            .unwrap();
+    /// Update the SSH identity file and options of a workspace
+    pub fn update_workspace_ssh(
+        &mut self,
+        name: &str,
+        identity_file: Option<String>,
+        options: Vec<String>,
+        reset: bool,
+    ) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
+
+        let ssh = workspace.ssh.get_or_insert_with(SshConfig::default);
+        if identity_file.is_some() {
+            ssh.identity_file = identity_file;
+        }
+        if reset {
+            ssh.options = options;
+        } else {
+            for option in options {
+                if !ssh.options.contains(&option) {
+                    ssh.options.push(option);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the SSH settings of a workspace
+    pub fn clear_workspace_ssh(&mut self, name: &str) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
+
+        workspace.ssh = None;
+
+        Ok(())
+    }
+
+    /// Update the committer override of a workspace
+    pub fn update_workspace_committer(
+        &mut self,
+        name: &str,
+        committer_name: Option<String>,
+        committer_email: Option<String>,
+    ) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
+
+        let committer = workspace
+            .committer
+            .get_or_insert_with(CommitterConfig::default);
+        if committer_name.is_some() {
+            committer.name = committer_name;
+        }
+        if committer_email.is_some() {
+            committer.email = committer_email;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the committer override of a workspace
+    pub fn clear_workspace_committer(&mut self, name: &str) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
+
+        workspace.committer = None;
+
+        Ok(())
+    }
+
+    /// Set and unset extra git config keys of a workspace
+    pub fn update_workspace_git(
+        &mut self,
+        name: &str,
+        set: Vec<(String, String)>,
+        unset: Vec<String>,
+    ) -> Result<()> {
+        let workspace = self
+            .workspaces
+            .get_mut(name)
+            .context(format!("Workspace '{}' not found", name))?;
+
+        for key in unset {
+            if workspace.git.remove(&key).is_none() {
+                bail!("Git key '{}' is not set in workspace '{}'", key, name);
+            }
+        }
+
+        for (key, value) in set {
+            if !is_valid_git_key(&key) {
+                bail!("Invalid git config key '{}', expected section.name", key);
+            }
+            if is_managed_key(&key) {
+                bail!(
+                    "Git key '{}' is managed by figgit, use the dedicated update option instead",
+                    key
+                );
+            }
+            workspace.git.insert(key, value);
+        }
+
+        Ok(())
+    }
+
+    /// Get a workspace by name
+    pub fn get_workspace(&self, name: &str) -> Result<&WorkspaceConfig> {
+        self.workspaces
+            .get(name)
+            .context(format!("Workspace '{}' not found", name))
+    }
+
+    /// Delete a workspace
+    pub fn delete_workspace(&mut self, name: &str) -> Result<()> {
+        if self.workspaces.remove(name).is_none() {
+            bail!("Workspace '{}' not found", name);
+        }
+        Ok(())
+    }
+
+    /// Find the workspace matching the given name and email best, with how it matches
+    ///
+    /// Closer kinds of match win; between equal ones, a workspace whose primary
+    /// name and email match wins over one matching through its aliases.
+    pub fn find_identity_match(
+        &self,
+        name: &str,
+        email: &str,
+    ) -> Option<(&String, &WorkspaceConfig, IdentityMatch)> {
+        self.workspaces
+            .iter()
+            .filter_map(|(workspace, config)| {
+                let kind = config.match_identity(name, email)?;
+                Some((workspace, config, kind))
+            })
+            .min_by_key(|(workspace, config, kind)| {
+                (
+                    *kind,
+                    config.name != name || config.email != email,
+                    *workspace,
+                )
+            })
+    }
+
+    /// Find every workspace pattern matching a URL, best ranked first
+    pub fn match_url(&self, url: &str) -> Vec<PatternMatch> {
+        self.match_rules(
+            |config| &config.patterns,
+            |pattern| pattern::matches_url(pattern, url),
+            pattern::specificity,
+        )
+    }
+
+    /// Find every workspace path rule matching a directory, best ranked first
+    pub fn match_path(&self, path: &Path) -> Vec<PatternMatch> {
+        self.match_rules(
+            |config| &config.paths,
+            |rule| pattern::matches_path(rule, path),
+            pattern::path_specificity,
+        )
+    }
+
+    fn match_rules(
+        &self,
+        rules: impl Fn(&WorkspaceConfig) -> &Vec<String>,
+        is_match: impl Fn(&str) -> bool,
+        specificity: impl Fn(&str) -> usize,
+    ) -> Vec<PatternMatch> {
+        let mut matches: Vec<PatternMatch> = self
+            .workspaces
+            .iter()
+            .flat_map(|(workspace, config)| {
+                rules(config)
+                    .iter()
+                    .filter(|rule| is_match(rule))
+                    .map(|rule| PatternMatch {
+                        workspace: workspace.clone(),
+                        pattern: rule.clone(),
+                        priority: config.priority.unwrap_or(0),
+                        specificity: specificity(rule),
+                    })
+            })
+            .collect();
+
+        // Workspaces live in a HashMap, so break ties by name to keep the order stable
+        matches.sort_by(|a, b| {
+            b.score()
+                .cmp(&a.score())
+                .then_with(|| (&a.workspace, &a.pattern).cmp(&(&b.workspace, &b.pattern)))
+        });
+        matches
+    }
+
+    /// Find every workspace pattern matching any of the given `(remote, url)` pairs,
+    /// best ranked first
+    pub fn match_remotes(&self, remotes: &[(String, String)]) -> Vec<RuleMatch> {
+        let mut matches: Vec<RuleMatch> = remotes
+            .iter()
+            .flat_map(|(remote, url)| {
+                self.match_url(url).into_iter().map(|matched| RuleMatch {
+                    source: MatchSource::Remote {
+                        remote: remote.clone(),
+                        url: url.clone(),
+                    },
+                    matched,
+                })
+            })
+            .collect();
+
+        // Stable sort keeps the per-URL order for equal scores
+        matches.sort_by_key(|m| std::cmp::Reverse(m.matched.score()));
+        matches
+    }
+
+    /// Find the rules matching a repository, best ranked first
+    ///
+    /// URL patterns are checked against the remotes first; path rules are only
+    /// used when no remote matches.
+    pub fn match_repository(&self, remotes: &[(String, String)], root: &Path) -> Vec<RuleMatch> {
+        let matches = self.match_remotes(remotes);
+        if !matches.is_empty() {
+            return matches;
+        }
+
+        self.match_path(root)
+            .into_iter()
+            .map(|matched| RuleMatch {
+                source: MatchSource::Path(root.to_path_buf()),
+                matched,
+            })
+            .collect()
+    }
+}
+
+/// Pick the best ranked match, failing when several workspaces tie for first place
+///
+/// `matches` must be sorted best first, as returned by [`Config::match_repository`].
+pub fn select_best_match(matches: &[RuleMatch]) -> Result<Option<&RuleMatch>> {
+    let Some(best) = matches.first() else {
+        return Ok(None);
+    };
+
+    let tied: Vec<&RuleMatch> = matches
+        .iter()
+        .take_while(|m| m.matched.score() == best.matched.score())
+        .collect();
+
+    if tied
+        .iter()
+        .any(|m| m.matched.workspace != best.matched.workspace)
+    {
+        let mut message = String::from("Ambiguous match, several workspaces rank equally:");
+        for m in tied {
+            message.push_str(&format!(
+                "\n  {} matches '{}' of workspace '{}' (priority {}, specificity {})",
+                m.source,
+                m.matched.pattern,
+                m.matched.workspace,
+                m.matched.priority,
+                m.matched.specificity
+            ));
+        }
+        message.push_str(
+            "\nSet a priority with 'figgit update <workspace> --priority <n>' \
+             or use 'figgit use <workspace>' explicitly.",
+        );
+        bail!(message);
+    }
+
+    Ok(Some(best))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_workspace() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        assert_eq!(config.workspaces.len(), 1);
+        let workspace = config.get_workspace("work").unwrap();
+        assert_eq!(workspace.name, "John Doe");
+        assert_eq!(workspace.email, "john@work.com");
+    }
+
+    #[test]
+    fn test_add_duplicate_workspace() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        let result = config.add_workspace("work", "Jane Doe", "jane@work.com");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_update_workspace() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        config
+            .update_workspace("work", Some("Jane Doe"), None)
+            .unwrap();
+        let workspace = config.get_workspace("work").unwrap();
+        assert_eq!(workspace.name, "Jane Doe");
+        assert_eq!(workspace.email, "john@work.com");
+
+        config
+            .update_workspace("work", None, Some("jane@work.com"))
+            .unwrap();
+        let workspace = config.get_workspace("work").unwrap();
+        assert_eq!(workspace.name, "Jane Doe");
+        assert_eq!(workspace.email, "jane@work.com");
+    }
+
+    #[test]
+    fn test_update_nonexistent_workspace() {
+        let mut config = Config::default();
+        let result = config.update_workspace("work", Some("John Doe"), None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_delete_workspace() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        config.delete_workspace("work").unwrap();
+        assert_eq!(config.workspaces.len(), 0);
+    }
+
+    #[test]
+    fn test_delete_nonexistent_workspace() {
+        let mut config = Config::default();
+        let result = config.delete_workspace("work");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_find_identity_match() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
         config
             .add_workspace("personal", "John Smith", "john@personal.com")
             .unwrap();
 
-        let result = config.find_matching_workspace("John Doe", "john@work.com");
+        let result = config.find_identity_match("John Doe", "john@work.com");
         assert!(result.is_some());
-        let (name, _) = result.unwrap();
+        let (name, _, _) = result.unwrap();
         assert_eq!(name, "work");
 
-        let result = config.find_matching_workspace("Unknown", "unknown@example.com");
+        let result = config.find_identity_match("Unknown", "unknown@example.com");
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_match_identity_kinds() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "José Doe", "john.doe@corp.com")
+            .unwrap();
+        let work = config.get_workspace("work").unwrap();
+
+        let kind = |name, email| work.match_identity(name, email);
+        assert_eq!(
+            kind("José Doe", "john.doe@corp.com"),
+            Some(IdentityMatch::Exact)
+        );
+        assert_eq!(
+            kind("Jose\u{301}  doe ", "john.doe+ci@Corp.com"),
+            Some(IdentityMatch::Normalized)
+        );
+        assert_eq!(
+            kind("J. Doe", "john.doe@corp.com"),
+            Some(IdentityMatch::EmailOnly)
+        );
+        assert_eq!(
+            kind("josé doe", "jose@home.org"),
+            Some(IdentityMatch::NameOnly)
+        );
+        assert_eq!(kind("Someone", "someone@else.org"), None);
+
+        assert!(work.matches_identity("JOSÉ DOE", "john.doe@CORP.COM"));
+        // The local part of an email keeps its case
+        assert!(!work.matches_identity("José Doe", "JOHN.DOE@corp.com"));
+        assert!(!work.matches_identity("J. Doe", "john.doe@corp.com"));
+        let (workspace, _, kind) = config
+            .find_identity_match("J. Doe", "john.doe@corp.com")
+            .unwrap();
+        assert_eq!(
+            (workspace.as_str(), kind),
+            ("work", IdentityMatch::EmailOnly)
+        );
+    }
+
+    #[test]
+    fn test_find_identity_match_with_aliases() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "jdoe@corp.com")
+            .unwrap();
+        config
+            .update_workspace_aliases(
+                "work",
+                vec![
+                    "john.doe@corp.com".to_string(),
+                    "1234+jdoe@users.noreply.github.com".to_string(),
+                ],
+                vec!["jdoe".to_string()],
+                false,
+            )
+            .unwrap();
+        config
+            .add_workspace("oss", "jdoe", "1234+jdoe@users.noreply.github.com")
+            .unwrap();
+
+        let matching = |name, email| {
+            config
+                .find_identity_match(name, email)
+                .filter(|(_, _, kind)| kind.is_full())
+                .map(|(workspace, _, _)| workspace.as_str())
+        };
+        assert_eq!(matching("John Doe", "john.doe@corp.com"), Some("work"));
+        assert_eq!(matching("jdoe", "jdoe@corp.com"), Some("work"));
+        assert_eq!(matching("Jane Doe", "jdoe@corp.com"), None);
+        // The primary identity of a workspace wins over the aliases of another
+        assert_eq!(
+            matching("jdoe", "1234+jdoe@users.noreply.github.com"),
+            Some("oss")
+        );
+
+        // Reset replaces only the lists that are given
+        config
+            .update_workspace_aliases("work", vec!["j@corp.com".to_string()], Vec::new(), true)
+            .unwrap();
+        let work = config.get_workspace("work").unwrap();
+        assert_eq!(work.alias_emails, vec!["j@corp.com"]);
+        assert_eq!(work.alias_names, vec!["jdoe"]);
+        assert_eq!(work.git_settings()[1].1, "jdoe@corp.com");
+    }
+
     #[test]
     fn test_serialization() {
         let mut config = Config::default();
@@ -373,4 +1296,601 @@ mod tests {
         assert_eq!(workspace.patterns.len(), 1);
         assert_eq!(workspace.patterns[0], "github.com/company/*");
     }
+
+    #[test]
+    fn test_match_remotes() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .add_workspace("personal", "John Doe", "john@personal.com")
+            .unwrap();
+        config
+            .update_workspace_patterns("work", vec!["github.com/company/*".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_patterns("personal", vec!["github.com/john/*".to_string()], false)
+            .unwrap();
+
+        let remotes = vec![
+            (
+                "origin".to_string(),
+                "https://github.com/company/repo.git".to_string(),
+            ),
+            (
+                "fork".to_string(),
+                "https://gitlab.com/john/repo.git".to_string(),
+            ),
+        ];
+        let matches = config.match_remotes(&remotes);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            matches[0].source,
+            MatchSource::Remote {
+                remote: "origin".to_string(),
+                url: "https://github.com/company/repo.git".to_string(),
+            }
+        );
+        assert_eq!(matches[0].matched.workspace, "work");
+        assert_eq!(matches[0].matched.pattern, "github.com/company/*");
+    }
+
+    #[test]
+    fn test_match_remotes_no_match() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_patterns("work", vec!["github.com/company/*".to_string()], false)
+            .unwrap();
+
+        let remotes = vec![(
+            "origin".to_string(),
+            "https://github.com/other/repo.git".to_string(),
+        )];
+        assert!(config.match_remotes(&remotes).is_empty());
+    }
+
+    fn config_with_overlapping_patterns() -> Config {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .add_workspace("personal", "John Doe", "john@personal.com")
+            .unwrap();
+        config
+            .update_workspace_patterns("work", vec!["github.com/company/*".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_patterns("personal", vec!["github.com/**".to_string()], false)
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn test_match_url_ranks_by_specificity() {
+        let config = config_with_overlapping_patterns();
+
+        let matches = config.match_url("git@github.com:company/repo.git");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].workspace, "work");
+        assert_eq!(matches[0].specificity, "github.com/company/".len());
+        assert_eq!(matches[1].workspace, "personal");
+
+        let matches = config.match_url("git@github.com:john/repo.git");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].workspace, "personal");
+    }
+
+    #[test]
+    fn test_match_url_priority_overrides_specificity() {
+        let mut config = config_with_overlapping_patterns();
+        config
+            .update_workspace_priority("personal", Some(10))
+            .unwrap();
+
+        let matches = config.match_url("https://github.com/company/repo");
+        assert_eq!(matches[0].workspace, "personal");
+        assert_eq!(matches[0].priority, 10);
+    }
+
+    #[test]
+    fn test_select_best_match() {
+        let config = config_with_overlapping_patterns();
+        let remotes = vec![(
+            "origin".to_string(),
+            "https://github.com/company/repo.git".to_string(),
+        )];
+
+        let matches = config.match_remotes(&remotes);
+        let best = select_best_match(&matches).unwrap().unwrap();
+        assert_eq!(best.matched.workspace, "work");
+
+        assert!(select_best_match(&[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_select_best_match_ambiguous() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .add_workspace("client", "John Doe", "john@client.com")
+            .unwrap();
+        config
+            .update_workspace_patterns("work", vec!["github.com/company/*".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_patterns("client", vec!["github.com/company/*".to_string()], false)
+            .unwrap();
+
+        let remotes = vec![(
+            "origin".to_string(),
+            "https://github.com/company/repo.git".to_string(),
+        )];
+        let matches = config.match_remotes(&remotes);
+        assert!(select_best_match(&matches).is_err());
+
+        config.update_workspace_priority("client", Some(1)).unwrap();
+        let matches = config.match_remotes(&remotes);
+        let best = select_best_match(&matches).unwrap().unwrap();
+        assert_eq!(best.matched.workspace, "client");
+    }
+
+    #[test]
+    fn test_priority_serialization() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(!serialized.contains("priority"));
+
+        config.update_workspace_priority("work", Some(5)).unwrap();
+        let serialized = toml::to_string(&config).unwrap();
+        let deserialized: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(
+            deserialized.get_workspace("work").unwrap().priority,
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn test_update_workspace_paths() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        config
+            .update_workspace_paths("work", vec!["~/work/**".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_paths("work", vec!["~/work/**".to_string()], false)
+            .unwrap();
+        assert_eq!(
+            config.get_workspace("work").unwrap().paths,
+            vec!["~/work/**"]
+        );
+
+        config
+            .update_workspace_paths("work", vec!["/srv/clients/acme/**".to_string()], true)
+            .unwrap();
+        assert_eq!(
+            config.get_workspace("work").unwrap().paths,
+            vec!["/srv/clients/acme/**"]
+        );
+    }
+
+    #[test]
+    fn test_match_repository_falls_back_to_paths() {
+        let mut config = config_with_overlapping_patterns();
+        config
+            .update_workspace_paths("work", vec!["/srv/work/**".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_paths("personal", vec!["/srv/**".to_string()], false)
+            .unwrap();
+
+        let root = Path::new("/srv/work/scratch");
+
+        // Without a remote, the most specific path rule wins
+        let matches = config.match_repository(&[], root);
+        let best = select_best_match(&matches).unwrap().unwrap();
+        assert_eq!(best.matched.workspace, "work");
+        assert_eq!(best.source, MatchSource::Path(root.to_path_buf()));
+
+        // A matching remote takes precedence over path rules
+        let remotes = vec![(
+            "origin".to_string(),
+            "git@github.com:john/repo.git".to_string(),
+        )];
+        let matches = config.match_repository(&remotes, root);
+        let best = select_best_match(&matches).unwrap().unwrap();
+        assert_eq!(best.matched.workspace, "personal");
+    }
+
+    #[test]
+    fn test_paths_serialization() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_paths("work", vec!["~/work/**".to_string()], false)
+            .unwrap();
+
+        let serialized = toml::to_string(&config).unwrap();
+        let deserialized: Config = toml::from_str(&serialized).unwrap();
+
+        let workspace = deserialized.get_workspace("work").unwrap();
+        assert_eq!(workspace.paths, vec!["~/work/**"]);
+    }
+
+    #[test]
+    fn test_update_workspace_signing() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        config
+            .update_workspace_signing(
+                "work",
+                SigningConfig {
+                    format: Some(SigningFormat::Ssh),
+                    key: Some("~/.ssh/id_work.pub".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        config
+            .update_workspace_signing(
+                "work",
+                SigningConfig {
+                    sign_commits: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let signing = config
+            .get_workspace("work")
+            .unwrap()
+            .signing
+            .clone()
+            .unwrap();
+        assert_eq!(signing.format, Some(SigningFormat::Ssh));
+        assert_eq!(signing.key.as_deref(), Some("~/.ssh/id_work.pub"));
+        assert_eq!(signing.sign_commits, Some(true));
+        assert_eq!(signing.sign_tags, None);
+        assert!(signing.signs_commits());
+
+        // A key alone does not turn commit signing on
+        let key_only = SigningConfig {
+            key: signing.key.clone(),
+            ..Default::default()
+        };
+        assert!(!key_only.signs_commits());
+
+        config.clear_workspace_signing("work").unwrap();
+        assert!(config.get_workspace("work").unwrap().signing.is_none());
+    }
+
+    #[test]
+    fn test_committer_override() {
+        let mut config = Config::default();
+        config
+            .add_workspace("ci", "John Doe", "john@work.com")
+            .unwrap();
+        config
+            .update_workspace_committer("ci", None, Some("bot@ci.work.com".to_string()))
+            .unwrap();
+
+        let workspace = config.get_workspace("ci").unwrap();
+        assert_eq!(
+            workspace.git_settings()[..3],
+            [
+                ("user.name".to_string(), "John Doe".to_string()),
+                ("user.email".to_string(), "john@work.com".to_string()),
+                ("committer.email".to_string(), "bot@ci.work.com".to_string()),
+            ]
+        );
+        assert_eq!(
+            workspace.identity_env(),
+            vec![
+                ("GIT_AUTHOR_NAME", "John Doe"),
+                ("GIT_AUTHOR_EMAIL", "john@work.com"),
+                ("GIT_COMMITTER_NAME", "John Doe"),
+                ("GIT_COMMITTER_EMAIL", "bot@ci.work.com"),
+            ]
+        );
+        assert!(workspace.owns_committer_email("bot@CI.work.com"));
+        assert!(workspace.owns_committer_email("john@work.com"));
+        assert!(!workspace.owns_email("bot@ci.work.com"));
+        assert!(workspace.matches_committer("john doe", "bot@Ci.work.com"));
+        assert!(!workspace.matches_committer("John Doe", "john@work.com"));
+        assert_eq!(
+            workspace.committer_summary().as_deref(),
+            Some("John Doe <bot@ci.work.com>")
+        );
+
+        config
+            .update_workspace_committer("ci", Some("CI Bot".to_string()), None)
+            .unwrap();
+        let committer = config.get_workspace("ci").unwrap().committer.clone();
+        assert_eq!(
+            committer,
+            Some(CommitterConfig {
+                name: Some("CI Bot".to_string()),
+                email: Some("bot@ci.work.com".to_string()),
+            })
+        );
+
+        config.clear_workspace_committer("ci").unwrap();
+        let workspace = config.get_workspace("ci").unwrap();
+        assert_eq!(workspace.git_settings().len(), 2);
+        assert_eq!(workspace.committer_summary(), None);
+        assert!(workspace.matches_committer("John Doe", "john@work.com"));
+        assert!(!workspace.matches_committer("John Doe", "bot@ci.work.com"));
+    }
+
+    #[test]
+    fn test_git_settings_with_signing() {
+        let workspace = WorkspaceConfig {
+            name: "John Doe".to_string(),
+            email: "john@work.com".to_string(),
+            signing: Some(SigningConfig {
+                format: Some(SigningFormat::X509),
+                key: Some("ABCD".to_string()),
+                sign_commits: Some(true),
+                sign_tags: Some(false),
+            }),
+            ..Default::default()
+        };
+
+        let settings = workspace.git_settings();
+        let keys: Vec<&str> = settings.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "user.name",
+                "user.email",
+                "gpg.format",
+                "user.signingkey",
+                "commit.gpgsign",
+                "tag.gpgsign"
+            ]
+        );
+        assert_eq!(settings[2].1, "x509");
+        assert_eq!(settings[5].1, "false");
+    }
+
+    #[test]
+    fn test_signing_serialization() {
+        let toml_str = r#"
+[workspaces.work]
+name = "John Doe"
+email = "john@work.com"
+
+[workspaces.work.signing]
+format = "ssh"
+key = "~/.ssh/id_work.pub"
+sign_commits = true
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let signing = config
+            .get_workspace("work")
+            .unwrap()
+            .signing
+            .clone()
+            .unwrap();
+        assert_eq!(signing.format, Some(SigningFormat::Ssh));
+        assert_eq!(signing.sign_commits, Some(true));
+
+        let serialized = toml::to_string(&config).unwrap();
+        let deserialized: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(
+            deserialized.get_workspace("work").unwrap().signing,
+            Some(signing)
+        );
+    }
+
+    #[test]
+    fn test_signing_format_from_str() {
+        assert_eq!(SigningFormat::from_str("ssh"), Some(SigningFormat::Ssh));
+        assert_eq!(SigningFormat::from_str("GPG"), Some(SigningFormat::Openpgp));
+        assert_eq!(SigningFormat::from_str("x509"), Some(SigningFormat::X509));
+        assert_eq!(SigningFormat::from_str("pgp2"), None);
+    }
+
+    #[test]
+    fn test_ssh_command() {
+        let ssh = SshConfig {
+            identity_file: Some("~/.ssh/id_work".to_string()),
+            options: vec!["StrictHostKeyChecking=accept-new".to_string()],
+        };
+        assert_eq!(
+            ssh.ssh_command().as_deref(),
+            Some("ssh -i ~/.ssh/id_work -o IdentitiesOnly=yes -o StrictHostKeyChecking=accept-new")
+        );
+
+        let ssh = SshConfig {
+            identity_file: Some("~/My Keys/id_work".to_string()),
+            options: Vec::new(),
+        };
+        assert_eq!(
+            ssh.ssh_command().as_deref(),
+            Some("ssh -i ~/'My Keys/id_work' -o IdentitiesOnly=yes")
+        );
+
+        assert_eq!(SshConfig::default().ssh_command(), None);
+    }
+
+    #[test]
+    fn test_update_workspace_ssh() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        config
+            .update_workspace_ssh(
+                "work",
+                Some("~/.ssh/id_work".to_string()),
+                Vec::new(),
+                false,
+            )
+            .unwrap();
+        config
+            .update_workspace_ssh("work", None, vec!["Port=2222".to_string()], false)
+            .unwrap();
+
+        let workspace = config.get_workspace("work").unwrap();
+        let ssh = workspace.ssh.clone().unwrap();
+        assert_eq!(ssh.identity_file.as_deref(), Some("~/.ssh/id_work"));
+        assert_eq!(ssh.options, vec!["Port=2222"]);
+        assert!(workspace.git_settings().contains(&(
+            "core.sshCommand".to_string(),
+            "ssh -i ~/.ssh/id_work -o IdentitiesOnly=yes -o Port=2222".to_string()
+        )));
+
+        config.clear_workspace_ssh("work").unwrap();
+        assert!(config.get_workspace("work").unwrap().ssh.is_none());
+    }
+
+    #[test]
+    fn test_update_workspace_git() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John Doe", "john@work.com")
+            .unwrap();
+
+        config
+            .update_workspace_git(
+                "work",
+                vec![
+                    ("pull.rebase".to_string(), "true".to_string()),
+                    (
+                        "http.https://proxy.example.com.proxy".to_string(),
+                        "p:3128".to_string(),
+                    ),
+                ],
+                Vec::new(),
+            )
+            .unwrap();
+        let settings = config.get_workspace("work").unwrap().git_settings();
+        assert_eq!(
+            settings.last(),
+            Some(&("pull.rebase".to_string(), "true".to_string()))
+        );
+
+        config
+            .update_workspace_git("work", Vec::new(), vec!["pull.rebase".to_string()])
+            .unwrap();
+        assert_eq!(config.get_workspace("work").unwrap().git.len(), 1);
+
+        // Unknown keys, invalid keys and keys with a dedicated option are rejected
+        assert!(config
+            .update_workspace_git("work", Vec::new(), vec!["pull.rebase".to_string()])
+            .is_err());
+        assert!(config
+            .update_workspace_git(
+                "work",
+                vec![("rebase".to_string(), "x".to_string())],
+                Vec::new()
+            )
+            .is_err());
+        assert!(config
+            .update_workspace_git(
+                "work",
+                vec![("user.Email".to_string(), "x".to_string())],
+                Vec::new()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_git_table_from_toml() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [workspaces.work]
+            name = "John Doe"
+            email = "john@work.com"
+
+            [workspaces.work.git]
+            "init.defaultBranch" = "main"
+            "pull.rebase" = true
+            core.hooksPath = "~/.githooks"
+            "#,
+        )
+        .unwrap();
+
+        let workspace = config.get_workspace("work").unwrap();
+        assert_eq!(workspace.git["init.defaultBranch"], "main");
+        assert_eq!(workspace.git["pull.rebase"], "true");
+        assert_eq!(workspace.git["core.hooksPath"], "~/.githooks");
+
+        // Keys are written back quoted and read the same way
+        let reloaded: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(reloaded.workspaces, config.workspaces);
+        assert!(config.remove_managed_git_keys().is_empty());
+    }
+
+    #[test]
+    fn test_remove_managed_git_keys() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [workspaces.work]
+            name = "John Doe"
+            email = "john@work.com"
+
+            [workspaces.work.git]
+            user.email = "john@personal.com"
+            "pull.rebase" = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.remove_managed_git_keys(),
+            vec![("work".to_string(), "user.email".to_string())]
+        );
+        let workspace = config.get_workspace("work").unwrap();
+        assert_eq!(
+            workspace.git.keys().collect::<Vec<_>>(),
+            vec!["pull.rebase"]
+        );
+    }
+
+    #[test]
+    fn test_validate_workspace_name() {
+        assert!(validate_workspace_name("work").is_ok());
+        assert!(validate_workspace_name("open source").is_ok());
+        for name in ["", ".hidden", "../evil", "a/b", "a\\b", "tab\t"] {
+            assert!(validate_workspace_name(name).is_err(), "{:?}", name);
+        }
+
+        let mut config = Config::default();
+        assert!(config
+            .add_workspace("../work", "John Doe", "john@work.com")
+            .is_err());
+        assert!(config.workspaces.is_empty());
+    }
+
+    #[test]
+    fn test_is_valid_git_key() {
+        assert!(is_valid_git_key("pull.rebase"));
+        assert!(is_valid_git_key("url.git@github.com:.insteadOf"));
+        assert!(!is_valid_git_key("rebase"));
+        assert!(!is_valid_git_key(".rebase"));
+        assert!(!is_valid_git_key("pull."));
+        assert!(!is_valid_git_key("pull.2rebase"));
+    }
 }
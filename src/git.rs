@@ -1,10 +1,152 @@
+use crate::url;
 use anyhow::{bail, Context, Result};
-use std::path::Path;
-use std::process::Command;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Directory git commands run in when set by `-C`, instead of the current directory
+static TARGET_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Run every git command in `dir`, like `git -C <dir>`
+pub fn set_target_dir(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        bail!("Cannot change to '{}': No such directory", dir.display());
+    }
+    if TARGET_DIR.set(dir.to_path_buf()).is_err() {
+        bail!("Target directory already set");
+    }
+    Ok(())
+}
+
+/// Start a git command in the target directory
+fn git_command() -> Command {
+    let mut command = Command::new("git");
+    if let Some(dir) = TARGET_DIR.get() {
+        command.arg("-C").arg(dir);
+    }
+    command
+}
+
+/// Start a git command in `dir`, relative to the target directory
+fn git_command_in(dir: &Path) -> Command {
+    let mut command = git_command();
+    command.arg("-C").arg(dir);
+    command
+}
+
+/// Start a git command in a repository found by `discover_repo_at`
+fn git_command_at(repo: &RepoLocation) -> Command {
+    git_command_in(repo.root())
+}
+
+/// Resolve a path the way a git command run in the target directory would
+pub fn resolve_path(path: &Path) -> Result<PathBuf> {
+    let mut resolved =
+        std::env::current_dir().context("Unable to determine the current directory")?;
+    if let Some(dir) = TARGET_DIR.get() {
+        resolved.push(dir);
+    }
+    resolved.push(path);
+    Ok(resolved)
+}
+
+/// Where a repository lives, as resolved by git
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoLocation {
+    /// Git directory of the current worktree
+    pub git_dir: PathBuf,
+    /// Git directory holding the config shared by every worktree
+    pub common_dir: PathBuf,
+    /// Top-level directory of the working tree, `None` for bare repositories
+    /// or from inside the git directory
+    pub work_tree: Option<PathBuf>,
+}
+
+impl RepoLocation {
+    /// The working tree top-level, or the git directory when there is none
+    pub fn root(&self) -> &Path {
+        self.work_tree.as_deref().unwrap_or(&self.git_dir)
+    }
+}
+
+/// Find the repository containing a directory, the way git itself does
+///
+/// Git walks up from `dir` and honours `GIT_DIR` and `GIT_WORK_TREE`, so this
+/// works from subdirectories, linked worktrees, submodules and bare repositories.
+pub fn discover_repo_at(dir: &Path) -> Result<RepoLocation> {
+    let output = git_command()
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "rev-parse",
+            "--absolute-git-dir",
+            "--git-common-dir",
+            "--is-inside-work-tree",
+        ])
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!("Not in a git repository");
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+    let mut lines = stdout.lines();
+    let (Some(git_dir), Some(common_dir), Some(inside_work_tree)) =
+        (lines.next(), lines.next(), lines.next())
+    else {
+        bail!("Unexpected output from git rev-parse");
+    };
+
+    // The common directory is relative to `dir` unless git made it absolute
+    let common_dir = resolve_path(dir)?.join(common_dir);
+    let common_dir = common_dir.canonicalize().unwrap_or(common_dir);
+
+    // --show-toplevel fails outside a working tree, so only ask for it inside one
+    let work_tree = if inside_work_tree == "true" {
+        let output = git_command()
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .context("Failed to execute git command")?;
+
+        if !output.status.success() {
+            bail!("Unable to determine the repository root");
+        }
+
+        let root = String::from_utf8(output.stdout)
+            .context("Invalid UTF-8 in git output")?
+            .trim()
+            .to_string();
+        Some(PathBuf::from(root))
+    } else {
+        None
+    };
+
+    Ok(RepoLocation {
+        git_dir: PathBuf::from(git_dir),
+        common_dir,
+        work_tree,
+    })
+}
 
-/// Check if the current directory is a git repository
+/// Find the repository containing the current directory
+pub fn discover_repo() -> Result<RepoLocation> {
+    discover_repo_at(Path::new("."))
+}
+
+/// Check if the current directory is inside a git repository
 pub fn is_git_repo() -> bool {
-    Path::new(".git").exists()
+    discover_repo().is_ok()
+}
+
+/// Get the top-level directory of the current repository
+///
+/// Bare repositories have no working tree, so their git directory is used.
+pub fn get_repo_root() -> Result<PathBuf> {
+    Ok(discover_repo()?.root().to_path_buf())
 }
 
 /// Get the current git user.name from local config
@@ -13,7 +155,7 @@ pub fn get_local_user_name() -> Result<String> {
         bail!("Not in a git repository");
     }
 
-    let output = Command::new("git")
+    let output = git_command()
         .args(["config", "--local", "user.name"])
         .output()
         .context("Failed to execute git command")?;
@@ -40,7 +182,7 @@ pub fn get_local_user_email() -> Result<String> {
         bail!("Not in a git repository");
     }
 
-    let output = Command::new("git")
+    let output = git_command()
         .args(["config", "--local", "user.email"])
         .output()
         .context("Failed to execute git command")?;
@@ -61,59 +203,725 @@ pub fn get_local_user_email() -> Result<String> {
     Ok(email)
 }
 
-/// Set the local git user.name
-pub fn set_local_user_name(name: &str) -> Result<()> {
-    if !is_git_repo() {
-        bail!("Not in a git repository");
+/// Set a key in the local git config of a repository, replacing all its values
+pub fn set_local_value_in(repo: &RepoLocation, key: &str, value: &str) -> Result<()> {
+    let status = git_command_at(repo)
+        .args(["config", "--local", "--replace-all", key, value])
+        .status()
+        .context("Failed to execute git command")?;
+
+    if !status.success() {
+        bail!("Failed to set git {}", key);
     }
 
-    let status = Command::new("git")
-        .args(["config", "--local", "user.name", name])
+    Ok(())
+}
+
+/// Get the effective value of a key in the current repository, from any config scope
+pub fn get_config_value(key: &str) -> Result<Option<String>> {
+    read_config_value(git_command(), key)
+}
+
+/// Get the effective value of a key in a repository, from any config scope
+pub fn get_config_value_in(repo: &RepoLocation, key: &str) -> Result<Option<String>> {
+    read_config_value(git_command_at(repo), key)
+}
+
+fn read_config_value(mut command: Command, key: &str) -> Result<Option<String>> {
+    let output = command
+        .args(["config", key])
+        .output()
+        .context("Failed to execute git command")?;
+
+    // git exits with status 1 when the key is not set
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let value = String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in git output")?
+        .trim()
+        .to_string();
+
+    Ok(Some(value))
+}
+
+/// Get both user.name and user.email from local config
+pub fn get_local_config() -> Result<(String, String)> {
+    let name = get_local_user_name()?;
+    let email = get_local_user_email()?;
+    Ok((name, email))
+}
+
+/// Get every value of a key in the local git config of a repository, empty when
+/// it is not set
+pub fn get_local_values_in(repo: &RepoLocation, key: &str) -> Result<Vec<String>> {
+    let output = git_command_at(repo)
+        .args(["config", "--local", "--get-all", key])
+        .output()
+        .context("Failed to execute git command")?;
+
+    // git exits with status 1 when the key is not set
+    match output.status.code() {
+        Some(0) => {}
+        Some(1) => return Ok(Vec::new()),
+        _ => bail!("Failed to read git {}", key),
+    }
+
+    let values = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+    Ok(values.lines().map(str::to_string).collect())
+}
+
+/// Get the last value of a key in the local git config of a repository, the one
+/// git uses
+pub fn get_local_value_in(repo: &RepoLocation, key: &str) -> Result<Option<String>> {
+    Ok(get_local_values_in(repo, key)?.pop())
+}
+
+/// Restore the local values of a key in a repository, unsetting it when `values`
+/// is empty
+pub fn restore_local_values_in(repo: &RepoLocation, key: &str, values: &[String]) -> Result<()> {
+    let status = git_command_at(repo)
+        .args(["config", "--local", "--unset-all", key])
+        .status()
+        .context("Failed to execute git command")?;
+
+    // Status 5 means the key was not set, which is fine
+    if !status.success() && status.code() != Some(5) {
+        bail!("Failed to unset git {}", key);
+    }
+
+    for value in values {
+        let status = git_command_at(repo)
+            .args(["config", "--local", "--add", key, value])
+            .status()
+            .context("Failed to execute git command")?;
+
+        if !status.success() {
+            bail!("Failed to restore git {}", key);
+        }
+    }
+
+    Ok(())
+}
+
+/// Give every key in the local config of a repository exactly the listed
+/// values, all or nothing, and return the values they replaced
+///
+/// An empty list unsets the key. The current values are read first; if any
+/// write fails, the keys written so far are restored and the error lists them.
+pub fn set_local_values_in(
+    repo: &RepoLocation,
+    targets: &[(String, Vec<String>)],
+) -> Result<Vec<(String, Vec<String>)>> {
+    let snapshot = get_local_snapshot_in(repo, targets)?;
+
+    apply_settings(
+        targets,
+        &snapshot,
+        |key, values| match values {
+            [value] => set_local_value_in(repo, key, value),
+            values => restore_local_values_in(repo, key, values),
+        },
+        |key, values| restore_local_values_in(repo, key, values),
+    )?;
+
+    Ok(snapshot)
+}
+
+/// Read the current local values of every key in `targets`
+pub fn get_local_snapshot_in<T>(
+    repo: &RepoLocation,
+    targets: &[(String, T)],
+) -> Result<Vec<(String, Vec<String>)>> {
+    targets
+        .iter()
+        .map(|(key, _)| Ok((key.clone(), get_local_values_in(repo, key)?)))
+        .collect()
+}
+
+/// Write `targets` in order, restoring `snapshot` for the keys touched so far
+/// when a write fails
+fn apply_settings(
+    targets: &[(String, Vec<String>)],
+    snapshot: &[(String, Vec<String>)],
+    mut write: impl FnMut(&str, &[String]) -> Result<()>,
+    mut restore: impl FnMut(&str, &[String]) -> Result<()>,
+) -> Result<()> {
+    for (index, (key, values)) in targets.iter().enumerate() {
+        let Err(err) = write(key, values) else {
+            continue;
+        };
+
+        // The failed write may have partially applied, so restore it as well
+        let mut rolled_back = Vec::new();
+        let mut not_restored = Vec::new();
+        for (key, values) in snapshot[..=index].iter().rev() {
+            match restore(key, values) {
+                Ok(()) => rolled_back.push(key.as_str()),
+                Err(_) => not_restored.push(key.as_str()),
+            }
+        }
+        rolled_back.reverse();
+        not_restored.reverse();
+
+        let mut message = format!(
+            "Failed to apply git config, rolled back: {}",
+            rolled_back.join(", ")
+        );
+        if !not_restored.is_empty() {
+            message.push_str(&format!("; could not restore: {}", not_restored.join(", ")));
+        }
+        return Err(err.context(message));
+    }
+
+    Ok(())
+}
+
+/// Get the name and effective URL of every remote configured in the local repository
+///
+/// URLs are returned after `url.<base>.insteadOf` rewriting, as git would use them.
+pub fn get_remote_urls() -> Result<Vec<(String, String)>> {
+    get_remote_urls_in(&discover_repo()?)
+}
+
+/// Get the name and effective URL of every remote of a repository
+pub fn get_remote_urls_in(repo: &RepoLocation) -> Result<Vec<(String, String)>> {
+    let output = git_command_at(repo)
+        .args(["config", "--local", "--get-regexp", r"^remote\..*\.url$"])
+        .output()
+        .context("Failed to execute git command")?;
+
+    // git exits with status 1 when no key matches, i.e. there are no remotes
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+    let rewrites = get_url_rewrites_in(repo)?;
+
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let (key, remote_url) = line.split_once(' ')?;
+            let remote = key.strip_prefix("remote.")?.strip_suffix(".url")?;
+            Some((
+                remote.to_string(),
+                url::rewrite(remote_url.trim(), &rewrites),
+            ))
+        })
+        .collect())
+}
+
+/// Get every `url.<base>.insteadOf` rule as `(base, prefix)` pairs
+pub fn get_url_rewrites() -> Result<Vec<(String, String)>> {
+    read_url_rewrites(git_command())
+}
+
+/// Get every `url.<base>.insteadOf` rule of a repository, as `(base, prefix)` pairs
+pub fn get_url_rewrites_in(repo: &RepoLocation) -> Result<Vec<(String, String)>> {
+    read_url_rewrites(git_command_at(repo))
+}
+
+fn read_url_rewrites(mut command: Command) -> Result<Vec<(String, String)>> {
+    let output = command
+        .args(["config", "--get-regexp", r"^url\..*\.insteadof$"])
+        .output()
+        .context("Failed to execute git command")?;
+
+    // git exits with status 1 when no rewrite rule is configured
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let (key, prefix) = line.split_once(' ')?;
+            let base = key.strip_prefix("url.")?.strip_suffix(".insteadof")?;
+            Some((base.to_string(), prefix.trim().to_string()))
+        })
+        .collect())
+}
+
+/// Get the path of the global git config file
+///
+/// Honours `GIT_CONFIG_GLOBAL`, falling back to `~/.gitconfig`.
+pub fn global_config_path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os("GIT_CONFIG_GLOBAL") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let home = dirs::home_dir().context("Unable to determine home directory")?;
+    Ok(home.join(".gitconfig"))
+}
+
+/// Get every `includeIf.<condition>.path` entry of a git config file as
+/// `(condition, path)` pairs, in file order
+pub fn get_conditional_includes(file: &Path) -> Result<Vec<(String, String)>> {
+    let output = git_command()
+        .arg("config")
+        .arg("--file")
+        .arg(file)
+        .args(["--null", "--get-regexp", r"^includeif\..*\.path$"])
+        .output()
+        .context("Failed to execute git command")?;
+
+    // git exits with status 1 when no key matches
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+
+    // With --null, each entry is `key\nvalue\0`, so conditions may contain spaces
+    Ok(stdout
+        .split('\0')
+        .filter_map(|entry| {
+            let (key, path) = entry.split_once('\n')?;
+            let condition = key.strip_prefix("includeif.")?.strip_suffix(".path")?;
+            Some((condition.to_string(), path.to_string()))
+        })
+        .collect())
+}
+
+/// Get a single value from a git config file
+pub fn get_value_from_file(file: &Path, key: &str) -> Result<String> {
+    let output = git_command()
+        .arg("config")
+        .arg("--file")
+        .arg(file)
+        .arg(key)
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!("No {} configured in {}", key, file.display());
+    }
+
+    let value = String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in git output")?
+        .trim()
+        .to_string();
+
+    if value.is_empty() {
+        bail!("No {} configured in {}", key, file.display());
+    }
+
+    Ok(value)
+}
+
+/// Get a key from the global git config
+pub fn get_global_value(key: &str) -> Result<Option<String>> {
+    let output = git_command()
+        .args(["config", "--global", key])
+        .output()
+        .context("Failed to execute git command")?;
+
+    // git exits with status 1 when the key is not set
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let value = String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in git output")?
+        .trim()
+        .to_string();
+
+    Ok(Some(value))
+}
+
+/// Set a key in the global git config
+pub fn set_global_value(key: &str, value: &str) -> Result<()> {
+    let status = git_command()
+        .args(["config", "--global", key, value])
         .status()
         .context("Failed to execute git command")?;
 
     if !status.success() {
-        bail!("Failed to set git user.name");
+        bail!("Failed to set global git {}", key);
+    }
+
+    Ok(())
+}
+
+/// Remove a key from the global git config
+pub fn unset_global_value(key: &str) -> Result<()> {
+    let status = git_command()
+        .args(["config", "--global", "--unset-all", key])
+        .status()
+        .context("Failed to execute git command")?;
+
+    // Status 5 means the key was not set, which is fine
+    if !status.success() && status.code() != Some(5) {
+        bail!("Failed to unset global git {}", key);
     }
 
     Ok(())
 }
 
-/// Set the local git user.email
-pub fn set_local_user_email(email: &str) -> Result<()> {
+/// Get the directory git runs the hooks of the current repository from,
+/// which honours `core.hooksPath`
+pub fn get_hooks_dir() -> Result<PathBuf> {
     if !is_git_repo() {
         bail!("Not in a git repository");
     }
 
-    let status = Command::new("git")
-        .args(["config", "--local", "user.email", email])
+    let output = git_command()
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!("Unable to determine the hooks directory");
+    }
+
+    let dir = String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in git output")?
+        .trim()
+        .to_string();
+
+    // The path is relative to the directory git ran in unless it is absolute
+    resolve_path(Path::new(&dir))
+}
+
+/// Get the name and email git would record for `var`, `GIT_AUTHOR_IDENT` or
+/// `GIT_COMMITTER_IDENT`, taking environment overrides into account
+///
+/// Returns `None` when git has no identity to use.
+pub fn get_ident(var: &str) -> Result<Option<(String, String)>> {
+    let output = git_command()
+        .args(["var", var])
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let ident = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+    Ok(parse_ident(ident.trim()))
+}
+
+/// Author and committer of a commit
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub subject: String,
+}
+
+/// Field separator of the `git log` format, which never appears in the fields
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// List the commits selected by `git log` revision arguments, newest first
+pub fn get_commits(revisions: &[String]) -> Result<Vec<CommitInfo>> {
+    let output = git_command()
+        .args(["log", "--format=%H%x1f%h%x1f%an%x1f%ae%x1f%cn%x1f%ce%x1f%s"])
+        .args(revisions)
+        .arg("--")
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to list commits: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().filter_map(parse_commit_line).collect())
+}
+
+fn parse_commit_line(line: &str) -> Option<CommitInfo> {
+    let fields: Vec<&str> = line.splitn(7, FIELD_SEPARATOR).collect();
+    let [id, short_id, author_name, author_email, committer_name, committer_email, subject] =
+        fields[..]
+    else {
+        return None;
+    };
+    Some(CommitInfo {
+        id: id.to_string(),
+        short_id: short_id.to_string(),
+        author_name: author_name.to_string(),
+        author_email: author_email.to_string(),
+        committer_name: committer_name.to_string(),
+        committer_email: committer_email.to_string(),
+        subject: subject.to_string(),
+    })
+}
+
+/// Check whether an object id names a commit present in the repository
+pub fn commit_exists(oid: &str) -> bool {
+    git_command()
+        .args(["cat-file", "-e", &format!("{}^{{commit}}", oid)])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Resolve a revision to the id of the commit it names
+pub fn resolve_commit(rev: &str) -> Result<String> {
+    let output = git_command()
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ])
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!("Unknown revision '{}'", rev);
+    }
+
+    Ok(String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in git output")?
+        .trim()
+        .to_string())
+}
+
+/// Check whether a full ref name, like `refs/heads/main`, exists
+pub fn ref_exists(reference: &str) -> bool {
+    git_command()
+        .args(["show-ref", "--verify", "--quiet", reference])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Run `git rev-list` with the given arguments and collect the listed ids
+pub fn rev_list(args: &[String]) -> Result<Vec<String>> {
+    let output = git_command()
+        .arg("rev-list")
+        .args(args)
+        .arg("--")
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to list commits: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+    Ok(stdout.lines().map(str::to_string).collect())
+}
+
+/// The parts of a commit object a rewrite carries over
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCommit {
+    pub tree: String,
+    pub parents: Vec<String>,
+    /// Author date in git's internal `<seconds> <offset>` format
+    pub author_date: String,
+    /// Encoding the message is written in, when it is not UTF-8
+    pub encoding: Option<String>,
+    pub message: Vec<u8>,
+}
+
+/// Read the tree, parents, author date, encoding and message of a commit
+pub fn get_raw_commit(id: &str) -> Result<RawCommit> {
+    let output = git_command()
+        .args(["cat-file", "commit", id])
+        .output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!("Failed to read commit {}", id);
+    }
+
+    parse_raw_commit(&output.stdout).context(format!("Failed to parse commit {}", id))
+}
+
+fn parse_raw_commit(content: &[u8]) -> Result<RawCommit> {
+    let split = content
+        .windows(2)
+        .position(|window| window == b"\n\n")
+        .map_or((content, &[][..]), |index| {
+            (&content[..index], &content[index + 2..])
+        });
+    let (headers, message) = split;
+    let headers = String::from_utf8_lossy(headers);
+
+    let mut commit = RawCommit {
+        tree: String::new(),
+        parents: Vec::new(),
+        author_date: String::new(),
+        encoding: None,
+        message: message.to_vec(),
+    };
+    for line in headers.lines() {
+        if let Some(tree) = line.strip_prefix("tree ") {
+            commit.tree = tree.to_string();
+        } else if let Some(parent) = line.strip_prefix("parent ") {
+            commit.parents.push(parent.to_string());
+        } else if let Some(author) = line.strip_prefix("author ") {
+            let (_, date) = author.rsplit_once('>').context("Invalid author header")?;
+            commit.author_date = date.trim().to_string();
+        } else if let Some(encoding) = line.strip_prefix("encoding ") {
+            commit.encoding = Some(encoding.to_string());
+        }
+    }
+
+    if commit.tree.is_empty() || commit.author_date.is_empty() {
+        bail!("Missing tree or author header");
+    }
+    Ok(commit)
+}
+
+/// Identities and settings used to write a new commit object
+pub struct NewCommit<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub committer_name: &'a str,
+    pub committer_email: &'a str,
+    /// Git config passed with `-c`, e.g. the signing settings
+    pub settings: &'a [(String, String)],
+    pub sign: bool,
+}
+
+/// Write a commit with the tree, author date, encoding and message of `commit`,
+/// the given parents and the identities of `new`, returning its id
+pub fn create_commit(commit: &RawCommit, parents: &[String], new: &NewCommit) -> Result<String> {
+    let mut command = git_command();
+    for (key, value) in new.settings {
+        command.arg("-c").arg(format!("{}={}", key, value));
+    }
+    // The message bytes are kept as they are, so keep the encoding they are in
+    if let Some(encoding) = &commit.encoding {
+        command
+            .arg("-c")
+            .arg(format!("i18n.commitEncoding={}", encoding));
+    }
+    command.args(["commit-tree", &commit.tree]);
+    for parent in parents {
+        command.args(["-p", parent]);
+    }
+    if new.sign {
+        command.arg("-S");
+    }
+
+    let mut child = command
+        .env("GIT_AUTHOR_NAME", new.name)
+        .env("GIT_AUTHOR_EMAIL", new.email)
+        .env("GIT_AUTHOR_DATE", &commit.author_date)
+        .env("GIT_COMMITTER_NAME", new.committer_name)
+        .env("GIT_COMMITTER_EMAIL", new.committer_email)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git command")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open git stdin")?
+        .write_all(&commit.message)
+        .context("Failed to pass the commit message to git")?;
+    let output = child
+        .wait_with_output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to write commit: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in git output")?
+        .trim()
+        .to_string())
+}
+
+/// Point a ref to `new`, failing if it no longer points to `old` when given
+pub fn update_ref(reference: &str, new: &str, old: Option<&str>, message: &str) -> Result<()> {
+    let status = git_command()
+        .args(["update-ref", "-m", message, reference, new])
+        .args(old)
         .status()
         .context("Failed to execute git command")?;
 
     if !status.success() {
-        bail!("Failed to set git user.email");
+        bail!("Failed to update {}", reference);
     }
 
     Ok(())
 }
 
-/// Get both user.name and user.email from local config
-pub fn get_local_config() -> Result<(String, String)> {
-    let name = get_local_user_name()?;
-    let email = get_local_user_email()?;
-    Ok((name, email))
+/// Map identities through the repository mailmap, as `git shortlog` shows them
+///
+/// The result holds one identity per input, in the same order.
+pub fn check_mailmap(identities: &[(String, String)]) -> Result<Vec<(String, String)>> {
+    let mut child = git_command()
+        .args(["check-mailmap", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git command")?;
+
+    let input: String = identities
+        .iter()
+        .map(|(name, email)| format!("{} <{}>\n", name, email))
+        .collect();
+    child
+        .stdin
+        .take()
+        .context("Failed to open git stdin")?
+        .write_all(input.as_bytes())
+        .context("Failed to pass the identities to git")?;
+    let output = child
+        .wait_with_output()
+        .context("Failed to execute git command")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to apply the mailmap: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mapped = stdout
+        .lines()
+        .map(|line| {
+            parse_ident(line)
+                .with_context(|| format!("Unexpected git check-mailmap output '{}'", line))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if mapped.len() != identities.len() {
+        bail!(
+            "git check-mailmap returned {} identities for {}",
+            mapped.len(),
+            identities.len()
+        );
+    }
+    Ok(mapped)
 }
 
-/// Set both user.name and user.email in local config
-pub fn set_local_config(name: &str, email: &str) -> Result<()> {
-    set_local_user_name(name)?;
-    set_local_user_email(email)?;
-    Ok(())
+/// Split `Name <email> 1700000000 +0100` into its name and email
+fn parse_ident(ident: &str) -> Option<(String, String)> {
+    let (name, rest) = ident.split_once('<')?;
+    let (email, _) = rest.split_once('>')?;
+    Some((name.trim().to_string(), email.trim().to_string()))
 }
 
 /// Get the git user.name from global config
 pub fn get_global_user_name() -> Result<String> {
-    let output = Command::new("git")
+    let output = git_command()
         .args(["config", "--global", "user.name"])
         .output()
         .context("Failed to execute git command")?;
@@ -136,7 +944,7 @@ pub fn get_global_user_name() -> Result<String> {
 
 /// Get the git user.email from global config
 pub fn get_global_user_email() -> Result<String> {
-    let output = Command::new("git")
+    let output = git_command()
         .args(["config", "--global", "user.email"])
         .output()
         .context("Failed to execute git command")?;
@@ -168,23 +976,22 @@ pub fn get_global_config() -> Result<(String, String)> {
 pub fn get_user_name_from_repo(repo_path: &str) -> Result<String> {
     let repo_path = Path::new(repo_path);
 
-    if !repo_path.exists() {
+    if !resolve_path(repo_path)?.exists() {
         bail!("Repository path does not exist: {}", repo_path.display());
     }
 
-    if !repo_path.is_dir() {
+    if !resolve_path(repo_path)?.is_dir() {
         bail!(
             "Repository path is not a directory: {}",
             repo_path.display()
         );
     }
 
-    let git_dir = repo_path.join(".git");
-    if !git_dir.exists() {
+    if discover_repo_at(repo_path).is_err() {
         bail!("Not a git repository: {}", repo_path.display());
     }
 
-    let output = Command::new("git")
+    let output = git_command()
         .args([
             "-C",
             repo_path.to_str().unwrap(),
@@ -221,23 +1028,22 @@ pub fn get_user_name_from_repo(repo_path: &str) -> Result<String> {
 pub fn get_user_email_from_repo(repo_path: &str) -> Result<String> {
     let repo_path = Path::new(repo_path);
 
-    if !repo_path.exists() {
+    if !resolve_path(repo_path)?.exists() {
         bail!("Repository path does not exist: {}", repo_path.display());
     }
 
-    if !repo_path.is_dir() {
+    if !resolve_path(repo_path)?.is_dir() {
         bail!(
             "Repository path is not a directory: {}",
             repo_path.display()
         );
     }
 
-    let git_dir = repo_path.join(".git");
-    if !git_dir.exists() {
+    if discover_repo_at(repo_path).is_err() {
         bail!("Not a git repository: {}", repo_path.display());
     }
 
-    let output = Command::new("git")
+    let output = git_command()
         .args([
             "-C",
             repo_path.to_str().unwrap(),
@@ -280,6 +1086,7 @@ pub fn get_config_from_repo(repo_path: &str) -> Result<(String, String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{git_init, TempDir, TempRepo};
 
     #[test]
     fn test_is_git_repo() {
@@ -296,9 +1103,8 @@ mod tests {
         let result = get_local_config();
         // In a non-git directory, this should fail
         // In a git directory without local config, this should also fail
-        if result.is_ok() {
+        if let Ok((name, email)) = result {
             // If it succeeded, we must be in a git repo with local config
-            let (name, email) = result.unwrap();
             assert!(!name.is_empty());
             assert!(!email.is_empty());
         }
@@ -310,9 +1116,8 @@ mod tests {
         // This is environment-dependent - global config may or may not be set
         let result = get_global_config();
 
-        if result.is_ok() {
+        if let Ok((name, email)) = result {
             // If it succeeded, both name and email should be non-empty
-            let (name, email) = result.unwrap();
             assert!(!name.is_empty(), "Global user.name should not be empty");
             assert!(!email.is_empty(), "Global user.email should not be empty");
         }
@@ -341,14 +1146,55 @@ mod tests {
             let result = get_config_from_repo(".");
 
             // This might fail if local config is not set, which is valid
-            if result.is_ok() {
-                let (name, email) = result.unwrap();
+            if let Ok((name, email)) = result {
                 assert!(!name.is_empty());
                 assert!(!email.is_empty());
             }
         }
     }
 
+    #[test]
+    fn test_get_remote_urls() {
+        // Remotes depend on the checkout; only check the shape of the result
+        if is_git_repo() {
+            let remotes = get_remote_urls().unwrap();
+            for (remote, url) in remotes {
+                assert!(!remote.is_empty());
+                assert!(!url.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_conditional_includes_from_file() {
+        let temp = TempDir::new("includes");
+        let file = temp.path().join(".gitconfig");
+        std::fs::write(
+            &file,
+            "[user]\n\tname = Jane\n\
+             [includeIf \"gitdir:~/work/\"]\n\tpath = .gitconfig-work\n\
+             [includeIf \"hasconfig:remote.*.url:git@github.com:my company/**\"]\n\tpath = ~/.gitconfig-work\n",
+        )
+        .unwrap();
+
+        let includes = get_conditional_includes(&file).unwrap();
+        let name = get_value_from_file(&file, "user.name").unwrap();
+        let email = get_value_from_file(&file, "user.email");
+
+        assert_eq!(
+            includes,
+            vec![
+                ("gitdir:~/work/".to_string(), ".gitconfig-work".to_string()),
+                (
+                    "hasconfig:remote.*.url:git@github.com:my company/**".to_string(),
+                    "~/.gitconfig-work".to_string()
+                ),
+            ]
+        );
+        assert_eq!(name, "Jane");
+        assert!(email.is_err());
+    }
+
     #[test]
     fn test_global_user_name_consistency() {
         // If we can get global user name, getting it twice should return the same value
@@ -366,4 +1212,186 @@ mod tests {
             assert_eq!(email1, email2, "Global user.email should be consistent");
         }
     }
+
+    #[test]
+    fn test_apply_settings_rolls_back_on_failure() {
+        let targets = vec![
+            ("user.name".to_string(), vec!["Jane".to_string()]),
+            ("user.email".to_string(), vec!["jane@work.com".to_string()]),
+            ("pull.rebase".to_string(), Vec::new()),
+        ];
+        let snapshot = vec![
+            ("user.name".to_string(), vec!["John".to_string()]),
+            ("user.email".to_string(), Vec::new()),
+            ("pull.rebase".to_string(), Vec::new()),
+        ];
+
+        let mut restored = Vec::new();
+        let result = apply_settings(
+            &targets,
+            &snapshot,
+            |key, _| match key {
+                "user.email" => bail!("Failed to set git {}", key),
+                _ => Ok(()),
+            },
+            |key, values| {
+                restored.push((key.to_string(), values.to_vec()));
+                Ok(())
+            },
+        );
+
+        let message = format!("{:#}", result.unwrap_err());
+        assert!(message.contains("rolled back: user.name, user.email"));
+        assert!(message.contains("Failed to set git user.email"));
+        // Keys after the failing one were never written, so they are left alone
+        assert_eq!(
+            restored,
+            vec![
+                ("user.email".to_string(), Vec::new()),
+                ("user.name".to_string(), vec!["John".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_settings_reports_failed_restores() {
+        let targets = vec![
+            ("user.name".to_string(), vec!["Jane".to_string()]),
+            ("user.email".to_string(), vec!["jane@work.com".to_string()]),
+        ];
+        let snapshot = vec![
+            ("user.name".to_string(), Vec::new()),
+            ("user.email".to_string(), Vec::new()),
+        ];
+
+        let result = apply_settings(
+            &targets,
+            &snapshot,
+            |key, _| match key {
+                "user.email" => bail!("Failed to set git {}", key),
+                _ => Ok(()),
+            },
+            |key, _| match key {
+                "user.name" => bail!("Failed to restore git {}", key),
+                _ => Ok(()),
+            },
+        );
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("rolled back: user.email"));
+        assert!(message.contains("could not restore: user.name"));
+    }
+
+    #[test]
+    fn test_set_local_values_replaces_multi_valued_keys() {
+        let repo = TempRepo::new("multi");
+        for value in ["one", "two"] {
+            let status = git_command_at(&repo)
+                .args(["config", "--local", "--add", "figgit.test", value])
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+
+        let targets = vec![("figgit.test".to_string(), vec!["three".to_string()])];
+        let replaced = set_local_values_in(&repo, &targets);
+        let current = get_local_values_in(&repo, "figgit.test");
+        let restored =
+            restore_local_values_in(&repo, "figgit.test", &replaced.as_ref().unwrap()[0].1)
+                .and_then(|()| get_local_values_in(&repo, "figgit.test"));
+
+        assert_eq!(
+            replaced.unwrap(),
+            vec![(
+                "figgit.test".to_string(),
+                vec!["one".to_string(), "two".to_string()]
+            )]
+        );
+        assert_eq!(current.unwrap(), vec!["three"]);
+        assert_eq!(restored.unwrap(), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_discover_repo_from_subdirectories_and_bare_repos() {
+        let temp = TempDir::new("discover");
+        let work = temp.path().join("work");
+        let bare = temp.path().join("bare.git");
+        std::fs::create_dir_all(work.join("sub/dir")).unwrap();
+        git_init(&[work.to_str().unwrap()]);
+        git_init(&["--bare", bare.to_str().unwrap()]);
+
+        let from_subdir = discover_repo_at(&work.join("sub/dir"));
+        let from_git_dir = discover_repo_at(&work.join(".git"));
+        let from_bare = discover_repo_at(&bare);
+
+        let work = work.canonicalize().unwrap_or(work);
+        let from_subdir = from_subdir.unwrap();
+        assert_eq!(from_subdir.work_tree.as_deref(), Some(work.as_path()));
+        assert_eq!(from_subdir.common_dir, work.join(".git"));
+        assert_eq!(from_subdir.root(), work);
+
+        let from_git_dir = from_git_dir.unwrap();
+        assert_eq!(from_git_dir.work_tree, None);
+        assert_eq!(from_git_dir.root(), work.join(".git"));
+
+        let from_bare = from_bare.unwrap();
+        assert_eq!(from_bare.work_tree, None);
+        assert!(from_bare.root().ends_with("bare.git"));
+    }
+
+    #[test]
+    fn test_parse_ident() {
+        assert_eq!(
+            parse_ident("Jane Doe <jane@work.com> 1700000000 +0100"),
+            Some(("Jane Doe".to_string(), "jane@work.com".to_string()))
+        );
+        assert_eq!(
+            parse_ident(" <> 1700000000 +0000"),
+            Some((String::new(), String::new()))
+        );
+        assert_eq!(parse_ident("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_commit_line() {
+        let commit = parse_commit_line(
+            "abc123\x1fabc\x1fJane\x1fjane@work.com\x1fGitHub\x1fnoreply@github.com\x1fFix: a\x1fb",
+        )
+        .unwrap();
+        assert_eq!(commit.short_id, "abc");
+        assert_eq!(commit.author_email, "jane@work.com");
+        assert_eq!(commit.committer_name, "GitHub");
+        assert_eq!(commit.subject, "Fix: a\x1fb");
+        assert_eq!(parse_commit_line("abc123"), None);
+    }
+
+    #[test]
+    fn test_parse_raw_commit() {
+        let content = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+                        parent 1111111111111111111111111111111111111111\n\
+                        parent 2222222222222222222222222222222222222222\n\
+                        author Jane <jane@home.org> 1700000000 +0100\n\
+                        committer Jane <jane@home.org> 1700000500 +0100\n\
+                        \n\
+                        Merge topic\n\nDetails\n";
+
+        let commit = parse_raw_commit(content).unwrap();
+        assert_eq!(commit.tree, "4b825dc642cb6eb9a060e54bf8d69288fbee4904");
+        assert_eq!(commit.parents.len(), 2);
+        assert_eq!(commit.author_date, "1700000000 +0100");
+        assert_eq!(commit.message, b"Merge topic\n\nDetails\n");
+        assert_eq!(commit.encoding, None);
+
+        let content = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+                        author Jos\xe9 <jose@home.org> 1700000000 +0100\n\
+                        committer Jos\xe9 <jose@home.org> 1700000000 +0100\n\
+                        encoding ISO-8859-1\n\
+                        \n\
+                        Caf\xe9\n";
+        let commit = parse_raw_commit(content).unwrap();
+        assert_eq!(commit.encoding.as_deref(), Some("ISO-8859-1"));
+        assert_eq!(commit.message, b"Caf\xe9\n");
+
+        assert!(parse_raw_commit(b"parent 1111\n\nmessage").is_err());
+    }
 }
@@ -0,0 +1,267 @@
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Marker identifying hooks written by figgit
+pub const HOOK_MARKER: &str = "# Generated by figgit ('figgit hook install'). Do not edit.";
+
+/// Suffix of the hook a local install replaced, chained and restored on uninstall
+pub const BACKUP_SUFFIX: &str = ".figgit-backup";
+
+/// Client-side hooks forwarded by a global install, since pointing
+/// `core.hooksPath` elsewhere stops git from running the repository hooks
+pub const CLIENT_HOOKS: &[&str] = &[
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "post-rewrite",
+    "pre-auto-gc",
+];
+
+/// Hooks running `figgit check`, with the arguments passed before the hook's own
+pub const CHECK_HOOKS: &[(&str, &[&str])] = &[
+    ("pre-commit", &["check", "--quiet"]),
+    ("pre-push", &["check", "--pre-push", "--quiet"]),
+];
+
+/// Get the figgit arguments a hook runs, if it runs a check
+pub fn check_args(name: &str) -> Option<&'static [&'static str]> {
+    CHECK_HOOKS
+        .iter()
+        .find(|(hook, _)| *hook == name)
+        .map(|(_, args)| *args)
+}
+
+/// File recording the `core.hooksPath` a global install replaced
+pub const PREVIOUS_HOOKS_PATH_FILE: &str = ".figgit-previous-hooks-path";
+
+/// Get the directory `core.hooksPath` points to after a global install
+pub fn global_hooks_dir() -> Result<PathBuf> {
+    let config_path = Config::config_path()?;
+    let dir = config_path
+        .parent()
+        .context("Unable to determine config directory")?;
+    Ok(dir.join("hooks"))
+}
+
+/// Hook that runs chained to whatever ran before the install
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chain<'a> {
+    /// A fixed hook file, e.g. the backup of the replaced local hook
+    File(&'a Path),
+    /// The hook of the same name in the repository's own hooks directory
+    RepositoryHook,
+}
+
+/// Render a hook script running `check` (if any) with figgit, then the chained hook
+///
+/// `check` is the list of arguments passed to figgit, e.g. `["check", "--quiet"]`,
+/// followed by the arguments git passes to the hook. The input of hooks reading
+/// stdin, like `pre-push`, is buffered so that both commands get it.
+pub fn render_hook(name: &str, figgit: &Path, check: Option<&[&str]>, chain: &Chain) -> String {
+    let mut script = format!("#!/bin/sh\n{}\n", HOOK_MARKER);
+    let buffer_stdin = check.is_some() && name == "pre-push";
+
+    if buffer_stdin {
+        // The trailing '.' keeps the final newline command substitution strips
+        script.push_str("input=$(cat; echo .)\ninput=${input%.}\n");
+    }
+    if let Some(args) = check {
+        let mut command = vec![sh_quote(&figgit.display().to_string())];
+        command.extend(args.iter().map(|arg| sh_quote(arg)));
+        command.push("\"$@\"".to_string());
+        let command = command.join(" ");
+        if buffer_stdin {
+            script.push_str(&format!("printf '%s' \"$input\" | {} || exit 1\n", command));
+        } else {
+            script.push_str(&format!("{} || exit 1\n", command));
+        }
+    }
+
+    match chain {
+        Chain::File(path) => {
+            script.push_str(&format!(
+                "previous={}\n",
+                sh_quote(&path.display().to_string())
+            ));
+        }
+        Chain::RepositoryHook => {
+            script.push_str(&format!(
+                "previous=\"$(git rev-parse --git-common-dir)/hooks/{}\"\n",
+                name
+            ));
+        }
+    }
+    if buffer_stdin {
+        script.push_str(
+            "if [ -x \"$previous\" ]; then\n    printf '%s' \"$input\" | \"$previous\" \"$@\"\nfi\n",
+        );
+    } else {
+        script.push_str("if [ -x \"$previous\" ]; then\n    exec \"$previous\" \"$@\"\nfi\n");
+    }
+
+    script
+}
+
+/// A ref update git passes to the `pre-push` hook on stdin
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefUpdate {
+    pub local_ref: String,
+    pub local_oid: String,
+    pub remote_ref: String,
+    pub remote_oid: String,
+}
+
+impl RefUpdate {
+    /// Check whether the update deletes the remote ref
+    pub fn is_deletion(&self) -> bool {
+        is_null_oid(&self.local_oid)
+    }
+
+    /// Check whether the remote ref does not exist yet
+    pub fn is_new(&self) -> bool {
+        is_null_oid(&self.remote_oid)
+    }
+}
+
+/// Parse the `<local ref> <local oid> <remote ref> <remote oid>` lines of `pre-push`
+pub fn parse_ref_updates(input: &str) -> Result<Vec<RefUpdate>> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [local_ref, local_oid, remote_ref, remote_oid] = fields[..] else {
+                bail!("Invalid ref update '{}'", line);
+            };
+            Ok(RefUpdate {
+                local_ref: local_ref.to_string(),
+                local_oid: local_oid.to_string(),
+                remote_ref: remote_ref.to_string(),
+                remote_oid: remote_oid.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Check for the all-zero object id git uses for a missing ref
+fn is_null_oid(oid: &str) -> bool {
+    oid.chars().all(|c| c == '0')
+}
+
+/// Check whether a hook file was generated by figgit
+pub fn is_figgit_hook(content: &str) -> bool {
+    content.lines().nth(1) == Some(HOOK_MARKER)
+}
+
+/// Quote a word for POSIX sh
+fn sh_quote(word: &str) -> String {
+    let is_plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-=:@+,".contains(c));
+    if is_plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_local_pre_commit_hook() {
+        let backup = Path::new("/repo/.git/hooks/pre-commit.figgit-backup");
+        let script = render_hook(
+            "pre-commit",
+            Path::new("/home/me/.cargo/bin/figgit"),
+            Some(&["check", "--quiet"]),
+            &Chain::File(backup),
+        );
+
+        assert!(is_figgit_hook(&script));
+        assert_eq!(
+            script,
+            "#!/bin/sh\n\
+             # Generated by figgit ('figgit hook install'). Do not edit.\n\
+             /home/me/.cargo/bin/figgit check --quiet \"$@\" || exit 1\n\
+             previous=/repo/.git/hooks/pre-commit.figgit-backup\n\
+             if [ -x \"$previous\" ]; then\n    exec \"$previous\" \"$@\"\nfi\n"
+        );
+    }
+
+    #[test]
+    fn test_render_forwarding_hook() {
+        let script = render_hook(
+            "commit-msg",
+            Path::new("/opt/my tools/figgit"),
+            None,
+            &Chain::RepositoryHook,
+        );
+
+        assert!(!script.contains("check"));
+        assert!(script.contains("previous=\"$(git rev-parse --git-common-dir)/hooks/commit-msg\""));
+
+        let script = render_hook(
+            "pre-commit",
+            Path::new("/opt/my tools/figgit"),
+            Some(&["check"]),
+            &Chain::RepositoryHook,
+        );
+        assert!(script.contains("'/opt/my tools/figgit' check \"$@\" || exit 1"));
+    }
+
+    #[test]
+    fn test_render_pre_push_hook_shares_stdin() {
+        let script = render_hook(
+            "pre-push",
+            Path::new("figgit"),
+            check_args("pre-push"),
+            &Chain::RepositoryHook,
+        );
+
+        assert!(script.contains("input=$(cat; echo .)"));
+        assert!(script
+            .contains("printf '%s' \"$input\" | figgit check --pre-push --quiet \"$@\" || exit 1"));
+        assert!(script.contains("printf '%s' \"$input\" | \"$previous\" \"$@\""));
+        assert!(!script.contains("exec"));
+        assert_eq!(check_args("commit-msg"), None);
+    }
+
+    #[test]
+    fn test_parse_ref_updates() {
+        let input = "refs/heads/main 1111111111111111111111111111111111111111 \
+                     refs/heads/main 2222222222222222222222222222222222222222\n\
+                     (delete) 0000000000000000000000000000000000000000 \
+                     refs/heads/old 3333333333333333333333333333333333333333\n\
+                     refs/heads/topic 4444444444444444444444444444444444444444 \
+                     refs/heads/topic 0000000000000000000000000000000000000000\n";
+
+        let updates = parse_ref_updates(input).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0].local_ref, "refs/heads/main");
+        assert!(!updates[0].is_deletion() && !updates[0].is_new());
+        assert!(updates[1].is_deletion());
+        assert!(updates[2].is_new());
+
+        assert!(parse_ref_updates("").unwrap().is_empty());
+        assert!(parse_ref_updates("refs/heads/main 1111").is_err());
+    }
+
+    #[test]
+    fn test_is_figgit_hook() {
+        assert!(!is_figgit_hook("#!/bin/sh\nnpx lint-staged\n"));
+        assert!(!is_figgit_hook(""));
+    }
+}
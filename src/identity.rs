@@ -0,0 +1,105 @@
+use serde::Serialize;
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+/// How closely a name and email match a workspace identity, best first
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdentityMatch {
+    /// Name and email are the workspace ones, or aliases, as written
+    Exact,
+    /// Name and email match once normalized
+    Normalized,
+    /// Only the email matches, the name does not
+    EmailOnly,
+    /// Only the name matches, the email does not
+    NameOnly,
+}
+
+impl IdentityMatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Normalized => "normalized",
+            Self::EmailOnly => "email-only",
+            Self::NameOnly => "name-only",
+        }
+    }
+
+    /// Check whether both the name and the email match
+    pub fn is_full(&self) -> bool {
+        matches!(self, Self::Exact | Self::Normalized)
+    }
+}
+
+impl fmt::Display for IdentityMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Normalize an email for comparison: trimmed, with a lowercased domain and
+/// without the `+tag` of plus-addressing
+///
+/// The local part keeps its case, which mail servers may honor:
+/// `John.Doe+github@Corp.com` becomes `John.Doe@corp.com`.
+pub fn normalize_email(email: &str) -> String {
+    let email = email.trim();
+    match email.rsplit_once('@') {
+        Some((local, domain)) => {
+            let local = local.split_once('+').map_or(local, |(base, _)| base);
+            format!("{}@{}", local, domain.to_lowercase())
+        }
+        None => email.to_string(),
+    }
+}
+
+/// Normalize a name for comparison: Unicode NFKC, lowercased, with runs of
+/// whitespace collapsed
+///
+/// Decomposed and precomposed accents compare equal, so `José` typed either way
+/// matches.
+pub fn normalize_name(name: &str) -> String {
+    let name: String = name.nfkc().collect();
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(normalize_email("John.Doe@Corp.com"), "John.Doe@corp.com");
+        assert_ne!(
+            normalize_email("John.Doe@corp.com"),
+            normalize_email("john.doe@corp.com")
+        );
+        assert_eq!(normalize_email(" jdoe+github@corp.com\t"), "jdoe@corp.com");
+        assert_eq!(normalize_email("not-an-email"), "not-an-email");
+        assert_eq!(normalize_email("a+b+c@x.org"), "a@x.org");
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("  John   Doe "), "john doe");
+        // Decomposed "e" + combining acute accent
+        assert_eq!(normalize_name("Jose\u{301}"), normalize_name("José"));
+        assert_eq!(normalize_name("Ｊｏｈｎ"), "john");
+    }
+
+    #[test]
+    fn test_identity_match_order() {
+        assert!(IdentityMatch::Exact < IdentityMatch::Normalized);
+        assert!(IdentityMatch::Normalized < IdentityMatch::EmailOnly);
+        assert!(IdentityMatch::EmailOnly < IdentityMatch::NameOnly);
+        assert!(IdentityMatch::Normalized.is_full() && !IdentityMatch::EmailOnly.is_full());
+        assert_eq!(
+            serde_json::to_string(&IdentityMatch::EmailOnly).unwrap(),
+            "\"email-only\""
+        );
+    }
+}
@@ -0,0 +1,635 @@
+use crate::block;
+use crate::config::{self, Config, WorkspaceConfig};
+use crate::pattern;
+use crate::url;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// First line of the block figgit manages in the global gitconfig
+pub const BEGIN_MARKER: &str =
+    "# BEGIN figgit managed block (generated by 'figgit install-includes', do not edit)";
+/// Last line of the block figgit manages in the global gitconfig
+pub const END_MARKER: &str = "# END figgit managed block";
+/// First line of every fragment figgit generates
+pub const FRAGMENT_HEADER: &str = "# Generated by figgit";
+
+/// A conditional include pointing at a workspace fragment
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludeEntry {
+    pub workspace: String,
+    pub condition: String,
+    pub path: PathBuf,
+}
+
+/// Get the directory holding the gitconfig fragments, next to the config file
+pub fn fragments_dir() -> Result<PathBuf> {
+    let config_path = Config::config_path()?;
+    let dir = config_path
+        .parent()
+        .context("Unable to determine config directory")?;
+    Ok(dir.to_path_buf())
+}
+
+/// Get the path of the gitconfig fragment generated for a workspace
+pub fn fragment_path(workspace: &str) -> Result<PathBuf> {
+    config::validate_workspace_name(workspace)?;
+    Ok(fragments_dir()?.join(format!("{}.gitconfig", workspace)))
+}
+
+/// Render the gitconfig fragment holding a workspace identity
+pub fn render_fragment(workspace: &str, config: &WorkspaceConfig) -> String {
+    let mut out = format!(
+        "{} for workspace '{}'. Do not edit.\n",
+        FRAGMENT_HEADER, workspace
+    );
+    out.push_str(&render_settings(&config.git_settings()));
+    out
+}
+
+/// Render `section.key` / `section.subsection.key` settings as gitconfig sections
+pub fn render_settings(settings: &[(String, String)]) -> String {
+    let mut out = String::new();
+    let mut current_section: Option<String> = None;
+
+    for (key, value) in settings {
+        let (section, name) = match key.rsplit_once('.') {
+            Some(split) => split,
+            None => continue,
+        };
+
+        if current_section.as_deref() != Some(section) {
+            match section.split_once('.') {
+                Some((section_name, subsection)) => {
+                    out.push_str(&format!("[{} \"{}\"]\n", section_name, escape(subsection)))
+                }
+                None => out.push_str(&format!("[{}]\n", section)),
+            }
+            current_section = Some(section.to_string());
+        }
+
+        out.push_str(&format!("\t{} = \"{}\"\n", name, escape(value)));
+    }
+
+    out
+}
+
+/// Build the conditional includes for every workspace usable as a file name
+///
+/// Git applies every matching include in order and the last one wins, so the
+/// entries are ordered from the weakest to the strongest match: path rules
+/// before URL patterns, then by priority and specificity as `figgit auto` does.
+pub fn include_entries(config: &Config) -> Result<Vec<IncludeEntry>> {
+    let mut ranked = Vec::new();
+
+    for (workspace, workspace_config) in &config.workspaces {
+        // Workspaces whose name cannot be a file name get no fragment
+        if config::validate_workspace_name(workspace).is_err() {
+            continue;
+        }
+        let path = fragment_path(workspace)?;
+        let priority = workspace_config.priority.unwrap_or(0);
+
+        for rule in &workspace_config.paths {
+            let rank = (false, priority, pattern::path_specificity(rule));
+            ranked.push((rank, gitdir_condition(rule), workspace, path.clone()));
+        }
+
+        for url_pattern in &workspace_config.patterns {
+            let rank = (true, priority, pattern::specificity(url_pattern));
+            for condition in hasconfig_conditions(url_pattern) {
+                ranked.push((rank, condition, workspace, path.clone()));
+            }
+        }
+    }
+
+    ranked.sort_by(|a, b| (a.0, a.2, &a.1).cmp(&(b.0, b.2, &b.1)));
+
+    Ok(ranked
+        .into_iter()
+        .map(|(_, condition, workspace, path)| IncludeEntry {
+            workspace: workspace.clone(),
+            condition,
+            path,
+        })
+        .collect())
+}
+
+/// Convert a directory path rule into a `gitdir:` condition
+pub fn gitdir_condition(rule: &str) -> String {
+    if rule.ends_with('/') || rule.ends_with("/**") {
+        format!("gitdir:{}", rule)
+    } else {
+        // The rule names the repository root; match its .git directory and below
+        format!("gitdir:{}/", rule)
+    }
+}
+
+/// Convert a URL pattern into `hasconfig:remote.*.url:` conditions
+///
+/// Git matches these against the raw remote URLs, so one condition is emitted
+/// for each URL form covered by figgit's canonical matching.
+pub fn hasconfig_conditions(url_pattern: &str) -> Vec<String> {
+    let canonical = url::canonicalize(url_pattern);
+
+    let Some((host, path)) = canonical
+        .split_once('/')
+        .filter(|(host, _)| !host.is_empty() && !host.starts_with('.'))
+    else {
+        return vec![format!("hasconfig:remote.*.url:{}", canonical)];
+    };
+
+    let mut paths = vec![path.to_string()];
+    if !path.ends_with('*') {
+        paths.push(format!("{}.git", path));
+    }
+
+    // `*` stands for a user or a port; git matches with WM_PATHNAME, so it
+    // never crosses a `/`
+    let authorities = [
+        host.to_string(),
+        format!("*@{}", host),
+        format!("{}:*", host),
+        format!("*@{}:*", host),
+    ];
+
+    let mut conditions = Vec::new();
+    for path in &paths {
+        for scheme in ["https", "http", "ssh", "git"] {
+            for authority in &authorities {
+                conditions.push(format!(
+                    "hasconfig:remote.*.url:{}://{}/{}",
+                    scheme, authority, path
+                ));
+            }
+        }
+        for scp_path in scp_paths(path) {
+            for authority in [host, &format!("*@{}", host)] {
+                conditions.push(format!("hasconfig:remote.*.url:{}:{}", authority, scp_path));
+            }
+        }
+    }
+    conditions
+}
+
+/// Rewrite a path so that git's wildmatch accepts it after the `host:` of an scp-like URL
+///
+/// Git only treats `**` as crossing directories next to a `/`, which the colon
+/// is not, so a leading `**` is spelled out as one or more directories instead.
+fn scp_paths(path: &str) -> Vec<String> {
+    match path.strip_prefix("**") {
+        Some("") => vec!["*".to_string(), "*/**".to_string()],
+        Some(rest) if rest.starts_with('/') => {
+            vec![rest[1..].to_string(), format!("*/**{}", rest)]
+        }
+        _ => vec![path.to_string()],
+    }
+}
+
+/// Render the managed block of conditional includes
+pub fn render_block(entries: &[IncludeEntry]) -> String {
+    let mut out = format!("{}\n", BEGIN_MARKER);
+    for entry in entries {
+        out.push_str(&format!("[includeIf \"{}\"]\n", escape(&entry.condition)));
+        out.push_str(&format!(
+            "\tpath = \"{}\"\n",
+            escape(&entry.path.to_string_lossy())
+        ));
+    }
+    out.push_str(END_MARKER);
+    out.push('\n');
+    out
+}
+
+/// Replace the managed block in a gitconfig, or append it if there is none
+///
+/// Everything outside the markers is left untouched.
+pub fn replace_block(existing: &str, block: &str) -> Result<String> {
+    block::replace(existing, BEGIN_MARKER, END_MARKER, block)
+}
+
+/// Check whether a file was generated by figgit as a workspace fragment
+pub fn is_generated_fragment(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .map(|content| content.starts_with(FRAGMENT_HEADER))
+        .unwrap_or(false)
+}
+
+/// A workspace rule recovered from an `includeIf` condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportedRule {
+    /// A directory path rule, from a `gitdir:` condition
+    Path(String),
+    /// A URL pattern, from a `hasconfig:remote.*.url:` condition
+    Pattern(String),
+}
+
+/// Convert an `includeIf` condition back into a workspace rule
+///
+/// `config_dir` is the directory of the file holding the condition, against
+/// which `./` paths are resolved. Conditions figgit has no rule for, such as
+/// `onbranch:` or the case-insensitive `gitdir/i:` (path rules are
+/// case-sensitive), yield `None`.
+pub fn rule_from_condition(condition: &str, config_dir: &Path) -> Option<ImportedRule> {
+    if let Some(url_pattern) = condition.strip_prefix("hasconfig:remote.*.url:") {
+        return Some(ImportedRule::Pattern(url::canonicalize(url_pattern)));
+    }
+
+    let gitdir = condition.strip_prefix("gitdir:")?;
+
+    let rule = if let Some(relative) = gitdir.strip_prefix("./") {
+        format!("{}/{}", config_dir.to_string_lossy(), relative)
+    } else if gitdir.starts_with('/') || gitdir.starts_with('~') {
+        gitdir.to_string()
+    } else {
+        // Like git, a relative pattern may match at any depth
+        format!("**/{}", gitdir)
+    };
+
+    // gitdir conditions name the .git directory, path rules name the repository root
+    let rule = rule
+        .strip_suffix("/.git/")
+        .or_else(|| rule.strip_suffix("/.git"))
+        .map(str::to_string)
+        .unwrap_or(rule);
+
+    Some(ImportedRule::Path(rule))
+}
+
+/// Resolve the `path` of an include the way git does: relative to the including
+/// file, with a leading `~/` standing for the home directory
+pub fn resolve_include_path(path: &str, config_dir: &Path) -> PathBuf {
+    if let (Some(rest), Some(home)) = (path.strip_prefix("~/"), dirs::home_dir()) {
+        return home.join(rest);
+    }
+    config_dir.join(path)
+}
+
+/// Derive a workspace name from an included file, e.g. `~/.gitconfig-work` gives `work`
+pub fn workspace_name_from_path(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_string_lossy().into_owned();
+    let mut name = file_name.trim_start_matches('.');
+
+    for prefix in ["gitconfig-", "gitconfig_", "gitconfig."] {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            name = rest;
+            break;
+        }
+    }
+    for suffix in [".gitconfig", ".inc", ".conf", ".config"] {
+        if let Some(rest) = name.strip_suffix(suffix) {
+            name = rest;
+            break;
+        }
+    }
+
+    if name.is_empty() || name == "gitconfig" {
+        // e.g. ~/work/.gitconfig: name it after its directory
+        let parent = path.parent()?.file_name()?.to_string_lossy().into_owned();
+        return Some(parent.trim_start_matches('.').to_string()).filter(|n| !n.is_empty());
+    }
+
+    Some(name.to_string())
+}
+
+/// Escape a string for use inside double quotes in a gitconfig file
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, TempRepo};
+    use std::process::Command;
+
+    fn entry(workspace: &str, condition: &str) -> IncludeEntry {
+        IncludeEntry {
+            workspace: workspace.to_string(),
+            condition: condition.to_string(),
+            path: PathBuf::from(format!("/home/john/.config/figgit/{}.gitconfig", workspace)),
+        }
+    }
+
+    #[test]
+    fn test_render_fragment() {
+        let config = WorkspaceConfig {
+            name: "John \"JD\" Doe".to_string(),
+            email: "john@work.com".to_string(),
+            ..Default::default()
+        };
+
+        let fragment = render_fragment("work", &config);
+        assert!(fragment.starts_with(FRAGMENT_HEADER));
+        assert!(fragment.contains("[user]\n\tname = \"John \\\"JD\\\" Doe\"\n"));
+        assert!(fragment.contains("\temail = \"john@work.com\"\n"));
+    }
+
+    #[test]
+    fn test_render_settings_with_subsection() {
+        let settings = vec![
+            ("user.name".to_string(), "John".to_string()),
+            (
+                "url.git@github.com:.insteadOf".to_string(),
+                "gh:".to_string(),
+            ),
+        ];
+
+        let rendered = render_settings(&settings);
+        assert_eq!(
+            rendered,
+            "[user]\n\tname = \"John\"\n[url \"git@github.com:\"]\n\tinsteadOf = \"gh:\"\n"
+        );
+    }
+
+    #[test]
+    fn test_gitdir_condition() {
+        assert_eq!(gitdir_condition("~/work/**"), "gitdir:~/work/**");
+        assert_eq!(gitdir_condition("~/work/"), "gitdir:~/work/");
+        assert_eq!(
+            gitdir_condition("/srv/clients/acme"),
+            "gitdir:/srv/clients/acme/"
+        );
+    }
+
+    #[test]
+    fn test_hasconfig_conditions() {
+        let conditions = hasconfig_conditions("github.com/company/*");
+        assert_eq!(conditions.len(), 18);
+        for form in [
+            "https://github.com/company/*",
+            "https://*@github.com/company/*",
+            "http://github.com:*/company/*",
+            "ssh://*@github.com:*/company/*",
+            "git://github.com/company/*",
+            "github.com:company/*",
+            "*@github.com:company/*",
+        ] {
+            assert!(
+                conditions.contains(&format!("hasconfig:remote.*.url:{}", form)),
+                "{}",
+                form
+            );
+        }
+
+        // Literal patterns also cover the `.git` suffix
+        let conditions = hasconfig_conditions("git@github.com:company/repo.git");
+        assert_eq!(conditions.len(), 36);
+        assert!(conditions
+            .contains(&"hasconfig:remote.*.url:*@github.com:company/repo.git".to_string()));
+
+        // A leading `**` is spelled out for the scp-like form
+        let conditions = hasconfig_conditions("github.com/**");
+        assert!(conditions.contains(&"hasconfig:remote.*.url:*@github.com:*".to_string()));
+        assert!(conditions.contains(&"hasconfig:remote.*.url:*@github.com:*/**".to_string()));
+
+        // Local paths are kept as they are
+        assert_eq!(
+            hasconfig_conditions("/srv/git/**"),
+            vec!["hasconfig:remote.*.url:/srv/git/**"]
+        );
+    }
+
+    #[test]
+    fn test_hasconfig_conditions_match_remote_forms_in_git() {
+        let temp = TempDir::new("hasconfig");
+        let fragment = temp.path().join("work.gitconfig");
+        std::fs::write(&fragment, "[user]\n\temail = john@work.com\n").unwrap();
+        let repo = TempRepo::new("hasconfig-repo");
+        let entries: Vec<_> = hasconfig_conditions("github.com/company/*")
+            .into_iter()
+            .map(|condition| IncludeEntry {
+                workspace: "work".to_string(),
+                condition,
+                path: fragment.clone(),
+            })
+            .collect();
+        let config_path = repo.common_dir.join("config");
+        let mut local = std::fs::read_to_string(&config_path).unwrap();
+        local.push_str(&render_block(&entries));
+        std::fs::write(&config_path, local).unwrap();
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(repo.root())
+                .env("GIT_CONFIG_GLOBAL", "/dev/null")
+                .env("GIT_CONFIG_NOSYSTEM", "1")
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        let cases = [
+            ("https://github.com/company/repo.git", true),
+            ("https://john@github.com/company/repo", true),
+            ("http://github.com/company/repo.git", true),
+            ("http://github.com:8080/company/repo.git", true),
+            ("ssh://git@github.com/company/repo.git", true),
+            ("ssh://git@github.com:22/company/repo.git", true),
+            ("ssh://github.com/company/repo", true),
+            ("git://github.com/company/repo.git", true),
+            ("git@github.com:company/repo.git", true),
+            ("github.com:company/repo.git", true),
+            ("git@github.com:other/repo.git", false),
+            ("https://github.com/company/group/repo.git", false),
+            ("https://gitlab.com/company/repo.git", false),
+        ];
+        for (remote_url, included) in cases {
+            assert!(git(&["config", "remote.origin.url", remote_url])
+                .status
+                .success());
+            let output = git(&["config", "--includes", "--get", "user.email"]);
+            let email = String::from_utf8_lossy(&output.stdout);
+            assert_eq!(email.trim() == "john@work.com", included, "{}", remote_url);
+        }
+    }
+
+    #[test]
+    fn test_include_entries_order() {
+        let mut config = Config::default();
+        config
+            .add_workspace("work", "John", "john@work.com")
+            .unwrap();
+        config
+            .add_workspace("personal", "John", "john@personal.com")
+            .unwrap();
+        config
+            .update_workspace_patterns("work", vec!["github.com/company/*".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_patterns("personal", vec!["github.com/**".to_string()], false)
+            .unwrap();
+        config
+            .update_workspace_paths("personal", vec!["~/src/**".to_string()], false)
+            .unwrap();
+
+        let entries = include_entries(&config).unwrap();
+        let workspaces: Vec<&str> = entries.iter().map(|e| e.workspace.as_str()).collect();
+
+        // Path rules first, then the least specific pattern, the most specific last
+        assert!(entries[0].condition.starts_with("gitdir:"));
+        assert_eq!(workspaces.last(), Some(&"work"));
+        assert_eq!(workspaces[1], "personal");
+    }
+
+    #[test]
+    fn test_include_entries_skip_names_unusable_as_file_names() {
+        let config: Config = toml::from_str(
+            r#"
+            [workspaces."../work"]
+            name = "John"
+            email = "john@work.com"
+            paths = ["~/work/**"]
+
+            [workspaces.personal]
+            name = "John"
+            email = "john@personal.com"
+            paths = ["~/src/**"]
+            "#,
+        )
+        .unwrap();
+
+        let entries = include_entries(&config).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].workspace, "personal");
+    }
+
+    #[test]
+    fn test_render_block() {
+        let block = render_block(&[entry("work", "gitdir:~/work/**")]);
+        assert_eq!(
+            block,
+            format!(
+                "{}\n[includeIf \"gitdir:~/work/**\"]\n\tpath = \"/home/john/.config/figgit/work.gitconfig\"\n{}\n",
+                BEGIN_MARKER, END_MARKER
+            )
+        );
+    }
+
+    #[test]
+    fn test_replace_block_appends_and_is_idempotent() {
+        let original = "[user]\n\tname = John\n";
+        let block = render_block(&[entry("work", "gitdir:~/work/**")]);
+
+        let once = replace_block(original, &block).unwrap();
+        assert!(once.starts_with(original));
+        assert!(once.ends_with(&block));
+
+        let twice = replace_block(&once, &block).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_replace_block_keeps_surrounding_content() {
+        let old_block = render_block(&[entry("work", "gitdir:~/work/**")]);
+        let existing = format!(
+            "[core]\n\teditor = vim\n\n{}[alias]\n\tco = checkout\n",
+            old_block
+        );
+
+        let new_block = render_block(&[entry("client", "gitdir:~/client/**")]);
+        let replaced = replace_block(&existing, &new_block).unwrap();
+
+        assert_eq!(
+            replaced,
+            format!(
+                "[core]\n\teditor = vim\n\n{}[alias]\n\tco = checkout\n",
+                new_block
+            )
+        );
+    }
+
+    #[test]
+    fn test_rule_from_condition() {
+        let dir = Path::new("/home/john");
+        let cases = [
+            ("gitdir:~/work/", ImportedRule::Path("~/work/".to_string())),
+            (
+                "gitdir:/srv/acme/api/.git",
+                ImportedRule::Path("/srv/acme/api".to_string()),
+            ),
+            ("gitdir:work/", ImportedRule::Path("**/work/".to_string())),
+            (
+                "gitdir:./clients/",
+                ImportedRule::Path("/home/john/clients/".to_string()),
+            ),
+            (
+                "hasconfig:remote.*.url:https://github.com/company/**",
+                ImportedRule::Pattern("github.com/company/**".to_string()),
+            ),
+            (
+                "hasconfig:remote.*.url:git@github.com:company/*",
+                ImportedRule::Pattern("github.com/company/*".to_string()),
+            ),
+        ];
+
+        for (condition, expected) in cases {
+            assert_eq!(
+                rule_from_condition(condition, dir),
+                Some(expected),
+                "converting {}",
+                condition
+            );
+        }
+
+        assert_eq!(rule_from_condition("onbranch:main", dir), None);
+        assert_eq!(rule_from_condition("gitdir/i:~/Work/**", dir), None);
+    }
+
+    #[test]
+    fn test_conditions_round_trip() {
+        for rule in ["~/work/**", "/srv/clients/acme/"] {
+            assert_eq!(
+                rule_from_condition(&gitdir_condition(rule), Path::new("/")),
+                Some(ImportedRule::Path(rule.to_string()))
+            );
+        }
+
+        for condition in hasconfig_conditions("github.com/company/*") {
+            assert_eq!(
+                rule_from_condition(&condition, Path::new("/")),
+                Some(ImportedRule::Pattern("github.com/company/*".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_include_path() {
+        let dir = Path::new("/home/john");
+        assert_eq!(
+            resolve_include_path("/etc/gitconfig-work", dir),
+            PathBuf::from("/etc/gitconfig-work")
+        );
+        assert_eq!(
+            resolve_include_path(".gitconfig-work", dir),
+            PathBuf::from("/home/john/.gitconfig-work")
+        );
+        assert_eq!(
+            resolve_include_path("~/.gitconfig-work", dir),
+            dirs::home_dir().unwrap().join(".gitconfig-work")
+        );
+    }
+
+    #[test]
+    fn test_workspace_name_from_path() {
+        let cases = [
+            ("/home/john/.gitconfig-work", "work"),
+            ("/home/john/.gitconfig_personal", "personal"),
+            ("/home/john/.config/git/acme.gitconfig", "acme"),
+            ("/home/john/.config/git/oss.inc", "oss"),
+            ("/home/john/client/.gitconfig", "client"),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(
+                workspace_name_from_path(Path::new(path)).as_deref(),
+                Some(expected),
+                "naming {}",
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::block;
+use anyhow::Result;
+
+/// First line of the block figgit manages in a `.mailmap`
+pub const BEGIN_MARKER: &str =
+    "# BEGIN figgit managed block (generated by 'figgit mailmap', do not edit)";
+/// Last line of the block figgit manages in a `.mailmap`
+pub const END_MARKER: &str = "# END figgit managed block";
+
+/// A `.mailmap` line mapping an identity found in commits onto the proper one
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MailmapEntry {
+    pub proper_name: String,
+    pub proper_email: String,
+    /// Email found in commits, or `None` to only fix the name used with `proper_email`
+    pub commit_email: Option<String>,
+}
+
+impl MailmapEntry {
+    /// Render the entry in one of the `.mailmap` forms git understands
+    pub fn render(&self) -> String {
+        match &self.commit_email {
+            Some(commit_email) => format!(
+                "{} <{}> <{}>",
+                self.proper_name, self.proper_email, commit_email
+            ),
+            None => format!("{} <{}>", self.proper_name, self.proper_email),
+        }
+    }
+}
+
+/// Render the managed block of mailmap entries
+pub fn render_block(entries: &[MailmapEntry]) -> String {
+    let mut out = format!("{}\n", BEGIN_MARKER);
+    for entry in entries {
+        out.push_str(&entry.render());
+        out.push('\n');
+    }
+    out.push_str(END_MARKER);
+    out.push('\n');
+    out
+}
+
+/// Replace the managed block in a `.mailmap`, or append it if there is none
+///
+/// Hand-written entries outside the markers are left untouched.
+pub fn replace_block(existing: &str, block: &str) -> Result<String> {
+    block::replace(existing, BEGIN_MARKER, END_MARKER, block)
+}
+
+/// Get the lowercased commit emails mapped by the hand-written entries of a
+/// `.mailmap`, i.e. the last email of every line outside the managed block
+pub fn hand_written_emails(content: &str) -> Vec<String> {
+    let mut emails = Vec::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        if line.starts_with(BEGIN_MARKER) {
+            in_block = true;
+        } else if in_block && line.starts_with(END_MARKER) {
+            in_block = false;
+        } else if !in_block {
+            let line = line.split('#').next().unwrap_or_default();
+            let email = line
+                .rsplit_once('<')
+                .and_then(|(_, rest)| rest.split_once('>'))
+                .map(|(email, _)| email.trim().to_lowercase());
+            emails.extend(email);
+        }
+    }
+
+    emails
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, email: &str, commit_email: Option<&str>) -> MailmapEntry {
+        MailmapEntry {
+            proper_name: name.to_string(),
+            proper_email: email.to_string(),
+            commit_email: commit_email.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_render_entries() {
+        assert_eq!(
+            entry("Jane Doe", "jane@company.com", Some("jane@home.org")).render(),
+            "Jane Doe <jane@company.com> <jane@home.org>"
+        );
+        assert_eq!(
+            entry("Jane Doe", "jane@company.com", None).render(),
+            "Jane Doe <jane@company.com>"
+        );
+    }
+
+    #[test]
+    fn test_replace_block_keeps_hand_written_entries() {
+        let existing = "# Team\nBob Smith <bob@company.com> <bob@laptop.local>\n";
+        let block = render_block(&[entry("Jane Doe", "jane@company.com", Some("jane@home.org"))]);
+
+        let merged = replace_block(existing, &block).unwrap();
+        assert!(merged.starts_with(existing));
+        assert!(merged.contains("Jane Doe <jane@company.com> <jane@home.org>\n"));
+
+        let updated = replace_block(&merged, &render_block(&[])).unwrap();
+        assert!(updated.starts_with(existing));
+        assert!(!updated.contains("jane@home.org"));
+        assert_eq!(
+            replace_block(&updated, &render_block(&[])).unwrap(),
+            updated
+        );
+    }
+
+    #[test]
+    fn test_hand_written_emails() {
+        let content = format!(
+            "Bob Smith <bob@company.com> <Bob@Laptop.local>\n\
+             Alice <alice@company.com>\n\
+             # Carol <carol@old.org>\n\
+             {}",
+            render_block(&[entry("Jane Doe", "jane@company.com", Some("jane@home.org"))])
+        );
+
+        assert_eq!(
+            hand_written_emails(&content),
+            vec!["bob@laptop.local", "alice@company.com"]
+        );
+    }
+}
@@ -1,21 +1,39 @@
+mod block;
 mod commands;
 mod config;
 mod git;
+mod hooks;
+mod identity;
+mod includes;
+mod mailmap;
 mod output;
+mod pattern;
+mod scan;
+mod state;
+#[cfg(test)]
+mod test_support;
+mod url;
 
 use anyhow::Result;
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
+use config::{SigningConfig, SigningFormat};
 use output::OutputFormat;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "figgit")]
 #[command(about = "Manage git configurations using workspace names", long_about = None)]
 struct Cli {
+    /// Run as if figgit was started in <PATH> instead of the current directory
+    #[arg(short = 'C', global = true, value_name = "PATH")]
+    directory: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
 
+// Parsed once per run, so the size of the `Update` variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Create a new workspace configuration
@@ -39,18 +57,126 @@ enum Commands {
         /// Git user email (optional)
         #[arg(short, long)]
         email: Option<String>,
+        /// Other email commits of this workspace are made with (can be specified multiple times)
+        #[arg(long = "alias-email", value_name = "EMAIL")]
+        alias_emails: Vec<String>,
+        /// Other spelling of the name commits of this workspace are made with (can be specified multiple times)
+        #[arg(long = "alias-name", value_name = "NAME")]
+        alias_names: Vec<String>,
+        /// Committer name, when commits are committed by someone other than the author
+        #[arg(long, value_name = "NAME")]
+        committer_name: Option<String>,
+        /// Committer email, when commits are committed by someone other than the author
+        #[arg(long, value_name = "EMAIL")]
+        committer_email: Option<String>,
+        /// Remove the committer override
+        #[arg(long, conflicts_with_all = ["committer_name", "committer_email"])]
+        clear_committer: bool,
         /// URL patterns for auto-detection (can be specified multiple times)
         #[arg(short, long = "pattern", value_name = "PATTERN")]
         patterns: Vec<String>,
-        /// Reset patterns list instead of appending
+        /// Directory path rules for auto-detection, e.g. "~/work/**" (can be specified multiple times)
+        #[arg(short = 'P', long = "path", value_name = "PATH")]
+        paths: Vec<String>,
+        /// Reset the given patterns, paths, aliases or SSH options list instead of appending
         #[arg(long)]
         reset: bool,
+        /// Explicit priority used to rank pattern matches (higher wins)
+        #[arg(long, allow_hyphen_values = true)]
+        priority: Option<i32>,
+        /// Remove the explicit priority
+        #[arg(long, conflicts_with = "priority")]
+        clear_priority: bool,
+        /// Signature format (openpgp, ssh, x509)
+        #[arg(long, value_name = "FORMAT", value_parser = ["openpgp", "ssh", "x509"])]
+        signing_format: Option<String>,
+        /// Key used to sign commits and tags (user.signingkey)
+        #[arg(long, value_name = "KEY")]
+        signing_key: Option<String>,
+        /// Sign every commit (commit.gpgsign)
+        #[arg(long, value_name = "BOOL")]
+        sign_commits: Option<bool>,
+        /// Sign every annotated tag (tag.gpgsign)
+        #[arg(long, value_name = "BOOL")]
+        sign_tags: Option<bool>,
+        /// Remove all signing settings
+        #[arg(
+            long,
+            conflicts_with_all = ["signing_format", "signing_key", "sign_commits", "sign_tags"]
+        )]
+        clear_signing: bool,
+        /// SSH identity file used for the workspace remotes (core.sshCommand)
+        #[arg(long, value_name = "PATH")]
+        ssh_key: Option<String>,
+        /// Extra ssh -o option, e.g. "Port=2222" (can be specified multiple times)
+        #[arg(long = "ssh-option", value_name = "OPTION")]
+        ssh_options: Vec<String>,
+        /// Remove all SSH settings
+        #[arg(long, conflicts_with_all = ["ssh_key", "ssh_options"])]
+        clear_ssh: bool,
+        /// Extra git config key to apply, e.g. "pull.rebase=true" (can be specified multiple times)
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        git_set: Vec<(String, String)>,
+        /// Remove an extra git config key (can be specified multiple times)
+        #[arg(long = "unset", value_name = "KEY")]
+        git_unset: Vec<String>,
+        /// Re-apply the workspace to every repository it was applied to
+        #[arg(long)]
+        propagate: bool,
+        /// Re-apply changed settings to the repositories using the workspace without asking
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Apply a workspace configuration to the local git repository
     Use {
         /// Name of the workspace
         workspace: String,
     },
+    /// Restore the git config the repository had before figgit applied a workspace
+    #[command(alias = "revert")]
+    Unuse {
+        /// Unset every key figgit applied instead of restoring the previous values
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Print GIT_AUTHOR_* and GIT_COMMITTER_* exports for a workspace, e.g. eval "$(figgit env work)"
+    Env {
+        /// Name of the workspace
+        workspace: String,
+    },
+    /// Detect the workspace from the repository remotes or location and apply it
+    Auto,
+    /// Detect and apply the workspace of every repository under a directory
+    ApplyAll {
+        /// Directory to search for repositories
+        root: PathBuf,
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Number of repositories processed in parallel (defaults to the number of CPUs)
+        #[arg(long, value_name = "N")]
+        jobs: Option<usize>,
+        /// Output format (default, json, table)
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<String>,
+        /// Output as JSON (shorthand for --format=json)
+        #[arg(short = 'j', long = "json", conflicts_with = "format")]
+        json: bool,
+    },
+    /// Show every workspace pattern matching a URL and how it ranks
+    Match {
+        /// Remote URL to match
+        url: String,
+        /// Output format (default, json, table)
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<String>,
+        /// Output as JSON (shorthand for --format=json)
+        #[arg(short = 'j', long = "json", conflicts_with = "format")]
+        json: bool,
+        /// Output as table (shorthand for --format=table)
+        #[arg(short = 't', long = "table", conflicts_with = "format")]
+        table: bool,
+    },
     /// View a workspace configuration
     View {
         /// Name of the workspace (optional, shows all if not provided)
@@ -77,30 +203,115 @@ enum Commands {
         #[arg(short = 't', long = "table", conflicts_with = "format")]
         table: bool,
     },
+    /// Verify that the identity in use matches the workspace the repository maps to
+    Check {
+        /// Check the commits of the ref updates read from stdin, as the pre-push hook
+        #[arg(long)]
+        pre_push: bool,
+        /// Remote pushed to (with --pre-push, defaults to origin)
+        #[arg(requires = "pre_push")]
+        remote: Option<String>,
+        /// URL pushed to (with --pre-push, defaults to the remote URL)
+        #[arg(requires = "pre_push")]
+        url: Option<String>,
+        /// Only print something when the check fails
+        #[arg(short, long)]
+        quiet: bool,
+    },
+    /// Classify the commit authors and committers against the expected workspace
+    Audit {
+        /// Revision range to scan, e.g. origin/main..HEAD (defaults to HEAD)
+        range: Option<String>,
+        /// Workspace the commits should belong to (defaults to the detected one)
+        #[arg(short, long)]
+        workspace: Option<String>,
+        /// Also fail when an identity belongs to no workspace
+        #[arg(long)]
+        strict: bool,
+        /// Output format (default, json, table)
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<String>,
+        /// Output as JSON (shorthand for --format=json)
+        #[arg(short = 'j', long = "json", conflicts_with = "format")]
+        json: bool,
+        /// Output as table (shorthand for --format=table)
+        #[arg(short = 't', long = "table", conflicts_with = "format")]
+        table: bool,
+    },
+    /// Rewrite the author and committer of unpushed commits to a workspace identity
+    FixAuthors {
+        /// Workspace whose identity the commits get
+        #[arg(short, long)]
+        workspace: String,
+        /// Commits to rewrite, e.g. origin/main..HEAD; the end must be HEAD or a local branch
+        range: String,
+        /// Also rewrite commits already on a remote-tracking branch
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Generate .mailmap entries mapping workspace identities onto the expected one
+    Mailmap {
+        /// Workspace to map onto (defaults to the detected one)
+        #[arg(short, long)]
+        workspace: Option<String>,
+        /// Merge the entries into the repository .mailmap instead of printing them
+        #[arg(long, conflicts_with = "check")]
+        write: bool,
+        /// Fail when the history contains workspace identities the .mailmap does not map
+        #[arg(long)]
+        check: bool,
+    },
+    /// Manage the pre-commit and pre-push hooks running 'figgit check'
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
     /// Show the current git configuration and compare with workspaces
     Status {
+        /// Report every repository under a directory instead of the current one
+        #[arg(short, long, value_name = "ROOT")]
+        recursive: Option<PathBuf>,
+        /// Number of repositories processed in parallel with --recursive (defaults to the number of CPUs)
+        #[arg(long, value_name = "N", requires = "recursive")]
+        jobs: Option<usize>,
         /// Output format (default, json, table)
         #[arg(long, value_name = "FORMAT")]
         format: Option<String>,
         /// Output as JSON (shorthand for --format=json)
         #[arg(short = 'j', long = "json", conflicts_with = "format")]
         json: bool,
+        /// Output as table (shorthand for --format=table)
+        #[arg(short = 't', long = "table", conflicts_with = "format")]
+        table: bool,
     },
     /// Delete a workspace configuration
     Delete {
         /// Name of the workspace
         workspace: String,
+        /// Restore the git config of the repositories using the workspace without asking
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Import a workspace configuration from git config
     Import {
         /// Name of the workspace
-        workspace: String,
+        #[arg(required_unless_present = "from_includes")]
+        workspace: Option<String>,
         /// Import from global git config instead of local
         #[arg(short, long)]
         global: bool,
         /// Import from a specific repository path
         #[arg(short, long)]
         from: Option<String>,
+        /// Create one workspace per identity included by includeIf sections of the global git config
+        #[arg(long, conflicts_with_all = ["workspace", "global", "from"])]
+        from_includes: bool,
+    },
+    /// Write includeIf entries for every workspace into the global gitconfig
+    InstallIncludes {
+        /// Print the fragments and the managed block instead of writing them
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Generate shell completions
     Completion {
@@ -110,6 +321,29 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum HookAction {
+    /// Install the pre-commit and pre-push hooks, chaining any existing hooks
+    Install {
+        /// Install for every repository through the global core.hooksPath
+        #[arg(short, long)]
+        global: bool,
+    },
+    /// Remove the hooks and restore the previous ones
+    Uninstall {
+        /// Remove the global hooks and restore the previous core.hooksPath
+        #[arg(short, long)]
+        global: bool,
+    },
+}
+
+/// Number of repositories processed in parallel when `--jobs` is not given
+fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+}
+
 fn determine_format(format: Option<&str>, json: bool, table: bool) -> OutputFormat {
     if json {
         OutputFormat::Json
@@ -122,9 +356,21 @@ fn determine_format(format: Option<&str>, json: bool, table: bool) -> OutputForm
     }
 }
 
+/// Parse a `key=value` argument, splitting on the first `=`
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if let Some(directory) = &cli.directory {
+        git::set_target_dir(directory)?;
+    }
+
     match cli.command {
         Commands::New {
             workspace,
@@ -137,20 +383,97 @@ fn main() -> Result<()> {
             workspace,
             name,
             email,
+            alias_emails,
+            alias_names,
+            committer_name,
+            committer_email,
+            clear_committer,
             patterns,
+            paths,
             reset,
+            priority,
+            clear_priority,
+            signing_format,
+            signing_key,
+            sign_commits,
+            sign_tags,
+            clear_signing,
+            ssh_key,
+            ssh_options,
+            clear_ssh,
+            git_set,
+            git_unset,
+            propagate,
+            yes,
         } => {
             commands::update_workspace(
                 &workspace,
-                name.as_deref(),
-                email.as_deref(),
-                patterns,
-                reset,
+                commands::WorkspaceUpdate {
+                    name,
+                    email,
+                    alias_emails,
+                    alias_names,
+                    committer_name,
+                    committer_email,
+                    clear_committer,
+                    patterns,
+                    paths,
+                    reset,
+                    priority,
+                    clear_priority,
+                    signing: SigningConfig {
+                        format: signing_format.as_deref().and_then(SigningFormat::from_str),
+                        key: signing_key,
+                        sign_commits,
+                        sign_tags,
+                    },
+                    clear_signing,
+                    ssh_key,
+                    ssh_options,
+                    clear_ssh,
+                    git_set,
+                    git_unset,
+                },
+                propagate,
+                yes,
             )?;
         }
         Commands::Use { workspace } => {
             commands::use_workspace(&workspace)?;
         }
+        Commands::Unuse { clear } => {
+            commands::unuse_workspace(clear)?;
+        }
+        Commands::Env { workspace } => {
+            commands::env_workspace(&workspace)?;
+        }
+        Commands::Auto => {
+            commands::auto_workspace()?;
+        }
+        Commands::ApplyAll {
+            root,
+            dry_run,
+            jobs,
+            format,
+            json,
+        } => {
+            let output_format = determine_format(format.as_deref(), json, false);
+            commands::apply_all(
+                &root,
+                dry_run,
+                jobs.unwrap_or_else(default_jobs),
+                output_format,
+            )?;
+        }
+        Commands::Match {
+            url,
+            format,
+            json,
+            table,
+        } => {
+            let output_format = determine_format(format.as_deref(), json, table);
+            commands::match_url(&url, output_format)?;
+        }
         Commands::View {
             workspace,
             format,
@@ -168,19 +491,86 @@ fn main() -> Result<()> {
             let output_format = determine_format(format.as_deref(), json, table);
             commands::list_workspaces(output_format)?;
         }
-        Commands::Status { format, json } => {
-            let output_format = determine_format(format.as_deref(), json, false);
-            commands::status(output_format)?;
+        Commands::Status {
+            recursive,
+            jobs,
+            format,
+            json,
+            table,
+        } => {
+            let output_format = determine_format(format.as_deref(), json, table);
+            match recursive {
+                Some(root) => commands::recursive_status(
+                    &root,
+                    jobs.unwrap_or_else(default_jobs),
+                    output_format,
+                )?,
+                None => commands::status(output_format)?,
+            }
+        }
+        Commands::Check {
+            pre_push,
+            remote,
+            url,
+            quiet,
+        } => {
+            if pre_push {
+                commands::check_pre_push(remote.as_deref(), url.as_deref(), quiet)?;
+            } else {
+                commands::check(quiet)?;
+            }
+        }
+        Commands::Audit {
+            range,
+            workspace,
+            strict,
+            format,
+            json,
+            table,
+        } => {
+            let output_format = determine_format(format.as_deref(), json, table);
+            commands::audit(
+                range.as_deref(),
+                workspace.as_deref(),
+                strict,
+                output_format,
+            )?;
         }
-        Commands::Delete { workspace } => {
-            commands::delete_workspace(&workspace)?;
+        Commands::FixAuthors {
+            workspace,
+            range,
+            force,
+        } => {
+            commands::fix_authors(&workspace, &range, force)?;
+        }
+        Commands::Mailmap {
+            workspace,
+            write,
+            check,
+        } => {
+            commands::mailmap(workspace.as_deref(), write, check)?;
+        }
+        Commands::Hook { action } => match action {
+            HookAction::Install { global } => commands::hook_install(global)?,
+            HookAction::Uninstall { global } => commands::hook_uninstall(global)?,
+        },
+        Commands::Delete { workspace, yes } => {
+            commands::delete_workspace(&workspace, yes)?;
         }
         Commands::Import {
             workspace,
             global,
             from,
+            from_includes,
         } => {
-            commands::import_workspace(&workspace, global, from.as_deref())?;
+            if from_includes {
+                commands::import_from_includes()?;
+            } else if let Some(workspace) = workspace {
+                commands::import_workspace(&workspace, global, from.as_deref())?;
+            }
+        }
+        Commands::InstallIncludes { dry_run } => {
+            commands::install_includes(dry_run)?;
         }
         Commands::Completion { shell } => {
             let mut cmd = Cli::command();
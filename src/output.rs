@@ -1,6 +1,9 @@
-use crate::config::WorkspaceConfig;
+use crate::config::{CommitterConfig, SigningConfig, SshConfig, WorkspaceConfig};
+use crate::identity::IdentityMatch;
+use crate::state::RegistryEntry;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
@@ -26,7 +29,23 @@ pub struct WorkspaceOutput {
     pub user_name: String,
     pub email: String,
     #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alias_emails: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alias_names: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub committer: Option<CommitterConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub patterns: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshConfig>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub git: BTreeMap<String, String>,
 }
 
 impl From<(&String, &WorkspaceConfig)> for WorkspaceOutput {
@@ -35,19 +54,180 @@ impl From<(&String, &WorkspaceConfig)> for WorkspaceOutput {
             name: name.clone(),
             user_name: config.name.clone(),
             email: config.email.clone(),
+            alias_emails: config.alias_emails.clone(),
+            alias_names: config.alias_names.clone(),
+            committer: config.committer.clone(),
             patterns: config.patterns.clone(),
+            paths: config.paths.clone(),
+            priority: config.priority,
+            signing: config.signing.clone(),
+            ssh: config.ssh.clone(),
+            git: config.git.clone(),
         }
     }
 }
 
 #[derive(Debug, Serialize)]
 pub struct StatusOutput {
+    /// Top-level directory of the repository, `None` outside of one
+    pub repository: Option<String>,
     pub current_name: Option<String>,
     pub current_email: Option<String>,
+    /// Local `committer.name` and `committer.email`, when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_committer_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_committer_email: Option<String>,
+    pub current_signing: Option<SigningConfig>,
+    /// Effective `core.sshCommand`, from any config scope
+    pub current_ssh_command: Option<String>,
+    /// Whether `core.sshCommand` differs from what the matching workspace expects
+    pub ssh_command_mismatch: bool,
+    /// `core.sshCommand` the matching workspace expects, set on a mismatch
+    /// unless it expects none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_ssh_command: Option<String>,
+    /// Set when the matching workspace expects a different committer, as `name <email>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_committer: Option<String>,
     pub matching_workspace: Option<String>,
+    /// How the identity matches `matching_workspace`
+    pub match_kind: Option<IdentityMatch>,
     pub available_workspaces: Vec<String>,
 }
 
+/// Status of one repository found by `status --recursive`
+#[derive(Debug, Serialize)]
+pub struct RepoStatusRow {
+    pub repository: String,
+    pub current_name: Option<String>,
+    pub current_email: Option<String>,
+    /// Workspace whose identity is configured
+    pub matching_workspace: Option<String>,
+    /// How the identity matches `matching_workspace`
+    pub match_kind: Option<IdentityMatch>,
+    /// Workspace selected by the remotes or path rules
+    pub expected_workspace: Option<String>,
+    pub mismatch: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecursiveStatusOutput {
+    pub root: String,
+    pub repositories: Vec<RepoStatusRow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatchCandidate {
+    pub workspace: String,
+    pub pattern: String,
+    pub priority: i32,
+    pub specificity: usize,
+    pub selected: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatchOutput {
+    pub url: String,
+    pub canonical_url: String,
+    pub candidates: Vec<MatchCandidate>,
+}
+
+/// What `apply-all` did to a repository
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyOutcome {
+    Changed,
+    Unchanged,
+    Skipped,
+    Error,
+}
+
+impl ApplyOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+            Self::Skipped => "skipped",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplyResult {
+    pub repository: String,
+    pub workspace: Option<String>,
+    pub outcome: ApplyOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplyAllOutput {
+    pub root: String,
+    pub dry_run: bool,
+    pub results: Vec<ApplyResult>,
+}
+
+/// How an identity found by `audit` relates to the workspace the repository expects
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditClass {
+    /// Belongs to another workspace
+    Leak,
+    /// Belongs to no workspace
+    Unknown,
+    /// Belongs to the expected workspace
+    Match,
+}
+
+impl AuditClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Leak => "leak",
+            Self::Unknown => "unknown",
+            Self::Match => "match",
+        }
+    }
+}
+
+/// An author or committer identity found in the audited commits
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AuditIdentity {
+    pub name: String,
+    pub email: String,
+    pub class: AuditClass,
+    /// Workspace owning the email, if any
+    pub workspace: Option<String>,
+    /// Number of commits authored with this identity
+    pub authored: usize,
+    /// Number of commits committed with this identity
+    pub committed: usize,
+    /// Newest commit using this identity
+    pub last_commit: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditSummary {
+    pub commits: usize,
+    pub identities: usize,
+    pub matching: usize,
+    pub leaks: usize,
+    pub unknown: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditOutput {
+    pub repository: String,
+    pub range: String,
+    pub expected_workspace: String,
+    pub identities: Vec<AuditIdentity>,
+    pub summary: AuditSummary,
+}
+
 pub fn print_workspaces(workspaces: &HashMap<String, WorkspaceConfig>, format: OutputFormat) {
     match format {
         OutputFormat::Json => {
@@ -72,81 +252,129 @@ fn print_table(workspaces: &HashMap<String, WorkspaceConfig>) {
         return;
     }
 
-    // Calculate column widths
     let mut workspaces_vec: Vec<_> = workspaces.iter().collect();
     workspaces_vec.sort_by_key(|(name, _)| *name);
 
-    let max_workspace = workspaces_vec
+    let with_aliases = workspaces_vec
         .iter()
-        .map(|(name, _)| name.len())
-        .max()
-        .unwrap_or(9)
-        .max(9);
-    let max_name = workspaces_vec
+        .any(|(_, config)| !config.alias_emails.is_empty() || !config.alias_names.is_empty());
+    let with_committer = workspaces_vec
         .iter()
-        .map(|(_, config)| config.name.len())
-        .max()
-        .unwrap_or(4)
-        .max(4);
-    let max_email = workspaces_vec
+        .any(|(_, config)| config.committer.is_some());
+    // Only show the signing column when some workspace signs
+    let with_signing = workspaces_vec
         .iter()
-        .map(|(_, config)| config.email.len())
-        .max()
-        .unwrap_or(5)
-        .max(5);
+        .any(|(_, config)| config.signing.is_some());
+    let with_git = workspaces_vec
+        .iter()
+        .any(|(_, config)| !config.git.is_empty());
 
-    // Print header
-    println!(
-        "┌─{}─┬─{}─┬─{}─┐",
-        "─".repeat(max_workspace),
-        "─".repeat(max_name),
-        "─".repeat(max_email)
-    );
-    println!(
-        "│ {:<width_ws$} │ {:<width_name$} │ {:<width_email$} │",
-        "Workspace",
-        "Name",
-        "Email",
-        width_ws = max_workspace,
-        width_name = max_name,
-        width_email = max_email
-    );
-    println!(
-        "├─{}─┼─{}─┼─{}─┤",
-        "─".repeat(max_workspace),
-        "─".repeat(max_name),
-        "─".repeat(max_email)
-    );
+    let rows: Vec<Vec<String>> = workspaces_vec
+        .iter()
+        .map(|(workspace_name, config)| {
+            let mut row = vec![
+                workspace_name.to_string(),
+                config.name.clone(),
+                config.email.clone(),
+            ];
+            if with_aliases {
+                let aliases: Vec<&str> = config
+                    .alias_names
+                    .iter()
+                    .chain(&config.alias_emails)
+                    .map(String::as_str)
+                    .collect();
+                row.push(aliases.join(", "));
+            }
+            if with_committer {
+                row.push(config.committer_summary().unwrap_or_default());
+            }
+            if with_signing {
+                row.push(
+                    config
+                        .signing
+                        .as_ref()
+                        .map(SigningConfig::summary)
+                        .unwrap_or_default(),
+                );
+            }
+            if with_git {
+                let settings: Vec<String> = config
+                    .git
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect();
+                row.push(settings.join(", "));
+            }
+            row
+        })
+        .collect();
 
-    // Print rows
-    for (i, (workspace_name, config)) in workspaces_vec.iter().enumerate() {
+    let mut headers = vec!["Workspace", "Name", "Email"];
+    if with_aliases {
+        headers.push("Aliases");
+    }
+    if with_committer {
+        headers.push("Committer");
+    }
+    if with_signing {
+        headers.push("Signing");
+    }
+    if with_git {
+        headers.push("Git config");
+    }
+    print_grid(&headers, &rows);
+}
+
+/// Print rows as a table with box-drawing borders
+fn print_grid(headers: &[&str], rows: &[Vec<String>]) {
+    // Calculate column widths
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(header.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let segments: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
         println!(
-            "│ {:<width_ws$} │ {:<width_name$} │ {:<width_email$} │",
-            workspace_name,
-            config.name,
-            config.email,
-            width_ws = max_workspace,
-            width_name = max_name,
-            width_email = max_email
+            "{}─{}─{}",
+            left,
+            segments.join(&format!("─{}─", middle)),
+            right
         );
+    };
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("│ {} │", padded.join(" │ "));
+    };
 
-        if i < workspaces_vec.len() - 1 {
-            println!(
-                "├─{}─┼─{}─┼─{}─┤",
-                "─".repeat(max_workspace),
-                "─".repeat(max_name),
-                "─".repeat(max_email)
-            );
+    // Print header
+    border("┌", "┬", "┐");
+    line(headers.to_vec());
+    border("├", "┼", "┤");
+
+    // Print rows
+    for (i, row) in rows.iter().enumerate() {
+        line(row.iter().map(String::as_str).collect());
+
+        if i < rows.len() - 1 {
+            border("├", "┼", "┤");
         }
     }
 
     // Print footer
-    println!(
-        "└─{}─┴─{}─┴─{}─┘",
-        "─".repeat(max_workspace),
-        "─".repeat(max_name),
-        "─".repeat(max_email)
-    );
+    border("└", "┴", "┘");
 }
 
 fn print_default_workspaces(workspaces: &HashMap<String, WorkspaceConfig>) {
@@ -164,15 +392,77 @@ fn print_default_workspaces(workspaces: &HashMap<String, WorkspaceConfig>) {
 
     for (name, workspace_config) in workspaces_vec {
         println!("  {}:", name);
-        println!("    Name:  {}", workspace_config.name);
-        println!("    Email: {}", workspace_config.email);
-        if !workspace_config.patterns.is_empty() {
-            println!("    Patterns:");
-            for pattern in &workspace_config.patterns {
-                println!("      - {}", pattern);
+        print_workspace_details(workspace_config, "    ");
+        println!();
+    }
+}
+
+/// Print the settings of a single workspace, one per line, with the given indent
+pub fn print_workspace_details(config: &WorkspaceConfig, indent: &str) {
+    println!("{}Name:  {}", indent, config.name);
+    println!("{}Email: {}", indent, config.email);
+    if !config.alias_emails.is_empty() {
+        println!("{}Alias emails: {}", indent, config.alias_emails.join(", "));
+    }
+    if !config.alias_names.is_empty() {
+        println!("{}Alias names: {}", indent, config.alias_names.join(", "));
+    }
+    if let Some(committer) = config.committer_summary() {
+        println!("{}Committer: {}", indent, committer);
+    }
+    if let Some(priority) = config.priority {
+        println!("{}Priority: {}", indent, priority);
+    }
+    if let Some(signing) = &config.signing {
+        println!("{}Signing: {}", indent, signing.summary());
+    }
+    if let Some(ssh_command) = config.ssh.as_ref().and_then(SshConfig::ssh_command) {
+        println!("{}SSH:   {}", indent, ssh_command);
+    }
+    if !config.patterns.is_empty() {
+        println!("{}Patterns:", indent);
+        for pattern in &config.patterns {
+            println!("{}  - {}", indent, pattern);
+        }
+    }
+    if !config.paths.is_empty() {
+        println!("{}Paths:", indent);
+        for path in &config.paths {
+            println!("{}  - {}", indent, path);
+        }
+    }
+    if !config.git.is_empty() {
+        println!("{}Git config:", indent);
+        for (key, value) in &config.git {
+            println!("{}  {} = {}", indent, key, value);
+        }
+    }
+}
+
+/// Print the repositories a workspace was applied to, after its details
+pub fn print_registered_repositories(
+    repositories: &[(&String, &RegistryEntry)],
+    format: OutputFormat,
+) {
+    if repositories.is_empty() {
+        return;
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = repositories
+                .iter()
+                .map(|(path, entry)| vec![path.to_string(), entry.applied_at.clone()])
+                .collect();
+            println!();
+            print_grid(&["Repository", "Applied"], &rows);
+        }
+        OutputFormat::Default | OutputFormat::Json => {
+            println!("  Repositories:");
+            for (path, entry) in repositories {
+                println!("    - {} (applied {})", path, entry.applied_at);
             }
         }
-        println!();
     }
 }
 
@@ -192,14 +482,65 @@ fn print_default_status(status: &StatusOutput) {
     println!("━━━━━━━━━━━━━━━━━━━━━━━━");
     println!();
 
+    let Some(repository) = &status.repository else {
+        println!("⚠ Not in a git repository");
+        return;
+    };
+    println!("Repository: {}", repository);
+    println!();
+
     if let (Some(name), Some(email)) = (&status.current_name, &status.current_email) {
         println!("Current local git config:");
         println!("  Name:  {}", name);
         println!("  Email: {}", email);
+        if status.current_committer_name.is_some() || status.current_committer_email.is_some() {
+            println!(
+                "  Committer: {} <{}>",
+                status.current_committer_name.as_deref().unwrap_or(name),
+                status.current_committer_email.as_deref().unwrap_or(email)
+            );
+        }
+        if let Some(signing) = &status.current_signing {
+            println!("  Signing: {}", signing.summary());
+        }
+        if let Some(ssh_command) = &status.current_ssh_command {
+            println!("  SSH:   {}", ssh_command);
+        }
         println!();
 
-        if let Some(workspace) = &status.matching_workspace {
-            println!("✓ Matches workspace: '{}'", workspace);
+        if let (Some(workspace), Some(kind)) = (&status.matching_workspace, status.match_kind) {
+            match kind {
+                IdentityMatch::Exact => println!("✓ Matches workspace: '{}'", workspace),
+                IdentityMatch::Normalized => println!(
+                    "✓ Matches workspace: '{}' (normalized: case, spacing or +tag differ)",
+                    workspace
+                ),
+                IdentityMatch::EmailOnly => println!(
+                    "⚠ Partially matches workspace: '{}' (email only, the name differs)",
+                    workspace
+                ),
+                IdentityMatch::NameOnly => println!(
+                    "⚠ Partially matches workspace: '{}' (name only, the email differs)",
+                    workspace
+                ),
+            }
+            if status.ssh_command_mismatch {
+                match &status.expected_ssh_command {
+                    Some(expected) => println!(
+                        "⚠ core.sshCommand differs from the workspace, which expects: {}",
+                        expected
+                    ),
+                    None => println!(
+                        "⚠ core.sshCommand is set in the repository, but the workspace uses none"
+                    ),
+                }
+            }
+            if let Some(expected) = &status.expected_committer {
+                println!(
+                    "⚠ Committer differs from the workspace, which expects: {}",
+                    expected
+                );
+            }
         } else {
             println!("⚠ Does not match any configured workspace");
 
@@ -226,6 +567,373 @@ fn print_default_status(status: &StatusOutput) {
     }
 }
 
+pub fn print_recursive_status(status: &RecursiveStatusOutput, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(status).unwrap());
+        }
+        OutputFormat::Table => {
+            print_recursive_status_table(status);
+        }
+        OutputFormat::Default => {
+            print_default_recursive_status(status);
+        }
+    }
+}
+
+/// Short state of a repository row: `ok`, `mismatch`, `unknown` or `error`
+fn repo_state(row: &RepoStatusRow) -> &'static str {
+    if row.error.is_some() {
+        "error"
+    } else if row.mismatch {
+        "mismatch"
+    } else if row.match_kind.is_some_and(|kind| !kind.is_full()) {
+        "partial"
+    } else if row.expected_workspace.is_none() && row.matching_workspace.is_none() {
+        "unknown"
+    } else {
+        "ok"
+    }
+}
+
+fn print_recursive_status_table(status: &RecursiveStatusOutput) {
+    if status.repositories.is_empty() {
+        println!("No git repository found under {}", status.root);
+        return;
+    }
+
+    let rows: Vec<Vec<String>> = status
+        .repositories
+        .iter()
+        .map(|row| {
+            vec![
+                relative_to(&row.repository, &status.root),
+                row.current_name.clone().unwrap_or_default(),
+                row.current_email.clone().unwrap_or_default(),
+                row.matching_workspace.clone().unwrap_or_default(),
+                row.match_kind
+                    .map(|kind| kind.to_string())
+                    .unwrap_or_default(),
+                row.expected_workspace.clone().unwrap_or_default(),
+                repo_state(row).to_string(),
+            ]
+        })
+        .collect();
+
+    print_grid(
+        &[
+            "Repository",
+            "Name",
+            "Email",
+            "Matches",
+            "Match",
+            "Expected",
+            "Status",
+        ],
+        &rows,
+    );
+    print_recursive_summary(status);
+}
+
+fn print_default_recursive_status(status: &RecursiveStatusOutput) {
+    if status.repositories.is_empty() {
+        println!("No git repository found under {}", status.root);
+        return;
+    }
+
+    for row in &status.repositories {
+        let repository = relative_to(&row.repository, &status.root);
+        let identity = match (&row.current_name, &row.current_email) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            _ => "no identity".to_string(),
+        };
+
+        if let Some(error) = &row.error {
+            let first_line = error.lines().next().unwrap_or_default();
+            let first_line = first_line.trim_end_matches(':');
+            println!("✗ {}: {} ({})", repository, identity, first_line);
+        } else if row.mismatch {
+            let matches = match (&row.matching_workspace, row.match_kind) {
+                (Some(workspace), Some(kind)) if !kind.is_full() => {
+                    format!("matches '{}' ({})", workspace, kind)
+                }
+                (Some(workspace), _) => format!("matches '{}'", workspace),
+                _ => "matches no workspace".to_string(),
+            };
+            println!(
+                "✗ {}: {} {}, expected '{}'",
+                repository,
+                identity,
+                matches,
+                row.expected_workspace.as_deref().unwrap_or_default()
+            );
+        } else if let Some(workspace) = &row.matching_workspace {
+            match row.match_kind {
+                Some(kind) if !kind.is_full() => println!(
+                    "⚠ {}: {} matches '{}' ({})",
+                    repository, identity, workspace, kind
+                ),
+                Some(IdentityMatch::Normalized) => println!(
+                    "✓ {}: {} matches '{}' (normalized)",
+                    repository, identity, workspace
+                ),
+                _ => println!("✓ {}: {} matches '{}'", repository, identity, workspace),
+            }
+        } else {
+            println!("? {}: {}, no workspace", repository, identity);
+        }
+    }
+
+    print_recursive_summary(status);
+}
+
+fn print_recursive_summary(status: &RecursiveStatusOutput) {
+    let mismatched = status
+        .repositories
+        .iter()
+        .filter(|row| row.mismatch)
+        .count();
+    let errors = status
+        .repositories
+        .iter()
+        .filter(|row| row.error.is_some())
+        .count();
+    println!();
+    println!(
+        "{} repositories, {} mismatched, {} errors",
+        status.repositories.len(),
+        mismatched,
+        errors
+    );
+}
+
+pub fn print_match(match_output: &MatchOutput, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(match_output).unwrap());
+        }
+        OutputFormat::Table => {
+            if match_output.candidates.is_empty() {
+                println!("No workspace pattern matches '{}'.", match_output.url);
+                return;
+            }
+
+            let rows: Vec<Vec<String>> = match_output
+                .candidates
+                .iter()
+                .map(|candidate| {
+                    vec![
+                        if candidate.selected { "✓" } else { "" }.to_string(),
+                        candidate.workspace.clone(),
+                        candidate.pattern.clone(),
+                        candidate.priority.to_string(),
+                        candidate.specificity.to_string(),
+                    ]
+                })
+                .collect();
+
+            print_grid(
+                &["", "Workspace", "Pattern", "Priority", "Specificity"],
+                &rows,
+            );
+        }
+        OutputFormat::Default => {
+            print_default_match(match_output);
+        }
+    }
+}
+
+fn print_default_match(match_output: &MatchOutput) {
+    println!("URL:       {}", match_output.url);
+    println!("Canonical: {}", match_output.canonical_url);
+    println!();
+
+    if match_output.candidates.is_empty() {
+        println!("⚠ No workspace pattern matches this URL");
+        return;
+    }
+
+    println!("Candidates (best first):");
+    for candidate in &match_output.candidates {
+        println!(
+            "  {} {} — '{}' (priority {}, specificity {})",
+            if candidate.selected { "✓" } else { " " },
+            candidate.workspace,
+            candidate.pattern,
+            candidate.priority,
+            candidate.specificity
+        );
+    }
+
+    if !match_output.candidates.iter().any(|c| c.selected) {
+        println!();
+        println!("⚠ Ambiguous: several workspaces rank equally");
+    }
+}
+
+pub fn print_apply_all(apply_output: &ApplyAllOutput, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(apply_output).unwrap());
+        }
+        OutputFormat::Table | OutputFormat::Default => {
+            print_default_apply_all(apply_output);
+        }
+    }
+}
+
+/// Show a repository path relative to the scanned root, which keeps tables narrow
+fn relative_to(path: &str, root: &str) -> String {
+    Path::new(path)
+        .strip_prefix(root)
+        .ok()
+        .map(|path| path.display().to_string())
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+fn print_default_apply_all(apply_output: &ApplyAllOutput) {
+    if apply_output.results.is_empty() {
+        println!("No git repository found under {}", apply_output.root);
+        return;
+    }
+
+    let rows: Vec<Vec<String>> = apply_output
+        .results
+        .iter()
+        .map(|result| {
+            vec![
+                relative_to(&result.repository, &apply_output.root),
+                result.workspace.clone().unwrap_or_default(),
+                result.outcome.as_str().to_string(),
+                // Multi-line errors only show their first line; JSON has the rest
+                result
+                    .detail
+                    .as_deref()
+                    .and_then(|detail| detail.lines().next())
+                    .unwrap_or_default()
+                    .to_string(),
+            ]
+        })
+        .collect();
+
+    print_grid(&["Repository", "Workspace", "Result", "Detail"], &rows);
+
+    let count = |outcome: ApplyOutcome| {
+        apply_output
+            .results
+            .iter()
+            .filter(|result| result.outcome == outcome)
+            .count()
+    };
+    println!();
+    println!(
+        "{} changed, {} unchanged, {} skipped, {} errors",
+        count(ApplyOutcome::Changed),
+        count(ApplyOutcome::Unchanged),
+        count(ApplyOutcome::Skipped),
+        count(ApplyOutcome::Error)
+    );
+    if apply_output.dry_run {
+        println!("Dry run: no repository was modified");
+    }
+}
+
+pub fn print_audit(audit: &AuditOutput, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(audit).unwrap());
+        }
+        OutputFormat::Table => {
+            print_audit_table(audit);
+        }
+        OutputFormat::Default => {
+            print_default_audit(audit);
+        }
+    }
+}
+
+fn print_audit_table(audit: &AuditOutput) {
+    let rows: Vec<Vec<String>> = audit
+        .identities
+        .iter()
+        .map(|identity| {
+            vec![
+                identity.name.clone(),
+                identity.email.clone(),
+                identity.class.as_str().to_string(),
+                identity.workspace.clone().unwrap_or_default(),
+                identity.authored.to_string(),
+                identity.committed.to_string(),
+                identity.last_commit.clone(),
+            ]
+        })
+        .collect();
+
+    if !rows.is_empty() {
+        print_grid(
+            &[
+                "Name",
+                "Email",
+                "Class",
+                "Workspace",
+                "Authored",
+                "Committed",
+                "Last commit",
+            ],
+            &rows,
+        );
+        println!();
+    }
+    print_audit_summary(audit);
+}
+
+fn print_default_audit(audit: &AuditOutput) {
+    println!(
+        "Audit of {} in {} (expected workspace '{}')",
+        audit.range, audit.repository, audit.expected_workspace
+    );
+    println!();
+
+    for identity in &audit.identities {
+        let (symbol, class) = match (identity.class, &identity.workspace) {
+            (AuditClass::Match, _) => ("✓", "match".to_string()),
+            (AuditClass::Leak, Some(workspace)) => ("✗", format!("leak from '{}'", workspace)),
+            (_, _) => ("?", "unknown".to_string()),
+        };
+        let mut roles = Vec::new();
+        if identity.authored > 0 {
+            roles.push(format!("{} authored", identity.authored));
+        }
+        if identity.committed > 0 {
+            roles.push(format!("{} committed", identity.committed));
+        }
+        println!(
+            "{} {} <{}>: {}, {} (last {})",
+            symbol,
+            identity.name,
+            identity.email,
+            class,
+            roles.join(", "),
+            identity.last_commit
+        );
+    }
+
+    if !audit.identities.is_empty() {
+        println!();
+    }
+    print_audit_summary(audit);
+}
+
+/// One line summary, stable for CI logs
+fn print_audit_summary(audit: &AuditOutput) {
+    let summary = &audit.summary;
+    println!(
+        "{} commits, {} identities: {} match, {} leaks, {} unknown",
+        summary.commits, summary.identities, summary.matching, summary.leaks, summary.unknown
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,7 +957,7 @@ mod tests {
         let config = WorkspaceConfig {
             name: "Test User".to_string(),
             email: "test@example.com".to_string(),
-            patterns: Vec::new(),
+            ..Default::default()
         };
         let workspace_name = "test".to_string();
         workspaces.insert(workspace_name.clone(), config);
@@ -260,4 +968,36 @@ mod tests {
         assert_eq!(output.user_name, "Test User");
         assert_eq!(output.email, "test@example.com");
     }
+
+    #[test]
+    fn test_repo_state_and_relative_path() {
+        let mut row = RepoStatusRow {
+            repository: "/src/work/api".to_string(),
+            current_name: Some("John Doe".to_string()),
+            current_email: Some("john@personal.com".to_string()),
+            matching_workspace: Some("personal".to_string()),
+            match_kind: Some(IdentityMatch::Exact),
+            expected_workspace: Some("work".to_string()),
+            mismatch: true,
+            error: None,
+        };
+        assert_eq!(repo_state(&row), "mismatch");
+
+        row.mismatch = false;
+        row.expected_workspace = None;
+        assert_eq!(repo_state(&row), "ok");
+
+        row.match_kind = Some(IdentityMatch::NameOnly);
+        assert_eq!(repo_state(&row), "partial");
+        row.match_kind = None;
+
+        row.matching_workspace = None;
+        assert_eq!(repo_state(&row), "unknown");
+
+        row.error = Some("Ambiguous match".to_string());
+        assert_eq!(repo_state(&row), "error");
+
+        assert_eq!(relative_to("/src/work/api", "/src"), "work/api");
+        assert_eq!(relative_to("/src", "/src"), ".");
+    }
 }
@@ -0,0 +1,245 @@
+use crate::url;
+use std::path::Path;
+
+/// Check whether `text` matches the glob `pattern`
+///
+/// `*` matches any run of characters except `/`, `**` matches any run of
+/// characters including `/`, and `?` matches a single character other than `/`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` may also stand for no directory at all
+            if rest.first() == Some(&'/') && match_from(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| match_from(rest, &text[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if match_from(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !text.is_empty() && text[0] != '/' && match_from(&pattern[1..], &text[1..]),
+        Some(c) => !text.is_empty() && text[0] == *c && match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Check whether a workspace URL pattern matches a remote URL
+///
+/// Both sides are canonicalized first, so a pattern written in any URL form
+/// matches every equivalent form of the remote.
+pub fn matches_url(pattern: &str, url: &str) -> bool {
+    glob_match(&url::canonicalize(pattern), &url::canonicalize(url))
+}
+
+/// Specificity of a URL pattern: the length of its literal prefix
+///
+/// `github.com/company/*` is more specific than `github.com/*`, so it wins when
+/// both match the same remote.
+pub fn specificity(pattern: &str) -> usize {
+    url::canonicalize(pattern)
+        .chars()
+        .take_while(|c| !matches!(c, '*' | '?'))
+        .count()
+}
+
+/// Expand a directory path rule into the absolute glob it stands for
+///
+/// A leading `~` is replaced by the home directory, and a trailing `/` means
+/// everything below that directory, as with git's `gitdir:` conditions.
+pub fn expand_path_rule(rule: &str) -> String {
+    let mut expanded = match (rule.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.to_string_lossy().replace('\\', "/"), rest)
+        }
+        _ => rule.to_string(),
+    };
+
+    if expanded.ends_with('/') {
+        expanded.push_str("**");
+    }
+
+    expanded
+}
+
+/// Check whether a directory path rule matches a repository location
+///
+/// A rule ending in `/**` also matches the directory itself, like the `gitdir:`
+/// condition it becomes matches that directory's `.git`.
+pub fn matches_path(rule: &str, path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.trim_end_matches('/');
+    let expanded = expand_path_rule(rule);
+    glob_match(&expanded, path)
+        || expanded
+            .strip_suffix("/**")
+            .is_some_and(|dir| glob_match(dir, path))
+}
+
+/// Specificity of a directory path rule: the length of its literal prefix once expanded
+pub fn path_specificity(rule: &str) -> usize {
+    expand_path_rule(rule)
+        .chars()
+        .take_while(|c| !matches!(c, '*' | '?'))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_literal() {
+        assert!(glob_match(
+            "github.com/company/repo",
+            "github.com/company/repo"
+        ));
+        assert!(!glob_match(
+            "github.com/company/repo",
+            "github.com/company/other"
+        ));
+    }
+
+    #[test]
+    fn test_glob_single_star_stays_in_segment() {
+        assert!(glob_match(
+            "github.com/company/*",
+            "github.com/company/repo"
+        ));
+        assert!(!glob_match(
+            "github.com/company/*",
+            "github.com/company/group/repo"
+        ));
+        assert!(glob_match("github.com/*/repo", "github.com/company/repo"));
+    }
+
+    #[test]
+    fn test_glob_double_star_crosses_segments() {
+        assert!(glob_match(
+            "gitlab.com/company/**",
+            "gitlab.com/company/group/repo"
+        ));
+        assert!(glob_match("gitlab.com/**/repo", "gitlab.com/repo"));
+        assert!(glob_match("gitlab.com/**/repo", "gitlab.com/a/b/repo"));
+        assert!(!glob_match("gitlab.com/**", "github.com/company/repo"));
+    }
+
+    #[test]
+    fn test_glob_question_mark() {
+        assert!(glob_match("github.com/repo-?", "github.com/repo-1"));
+        assert!(!glob_match("github.com/repo-?", "github.com/repo-10"));
+    }
+
+    #[test]
+    fn test_matches_url() {
+        assert!(matches_url(
+            "github.com/company/*",
+            "https://github.com/company/repo.git"
+        ));
+        assert!(!matches_url(
+            "github.com/company/*",
+            "https://github.com/other/repo.git"
+        ));
+    }
+
+    #[test]
+    fn test_matches_url_across_forms() {
+        let urls = [
+            "git@github.com:company/repo.git",
+            "ssh://git@github.com:22/company/repo",
+            "https://user@github.com/company/repo.git",
+        ];
+
+        for url in urls {
+            assert!(matches_url("github.com/company/*", url), "{}", url);
+            assert!(matches_url("git@github.com:company/*", url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_specificity() {
+        assert_eq!(specificity("github.com/*"), "github.com/".len());
+        assert_eq!(
+            specificity("github.com/company/*"),
+            "github.com/company/".len()
+        );
+        assert_eq!(
+            specificity("git@github.com:company/*"),
+            "github.com/company/".len()
+        );
+        assert_eq!(
+            specificity("github.com/company/repo"),
+            "github.com/company/repo".len()
+        );
+        assert!(specificity("github.com/company/*") > specificity("github.com/**"));
+    }
+
+    #[test]
+    fn test_expand_path_rule() {
+        let home = dirs::home_dir()
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/");
+        assert_eq!(expand_path_rule("~/work/**"), format!("{}/work/**", home));
+        assert_eq!(
+            expand_path_rule("/srv/clients/acme/"),
+            "/srv/clients/acme/**"
+        );
+        assert_eq!(expand_path_rule("~other/work"), "~other/work");
+    }
+
+    #[test]
+    fn test_matches_path() {
+        let home = dirs::home_dir().unwrap();
+        assert!(matches_path(
+            "~/work/**",
+            &home.join("work").join("project")
+        ));
+        assert!(matches_path(
+            "/srv/clients/acme/**",
+            Path::new("/srv/clients/acme/api/backend")
+        ));
+        assert!(matches_path(
+            "/srv/clients/acme/",
+            Path::new("/srv/clients/acme/api")
+        ));
+        assert!(matches_path("/srv/*/acme", Path::new("/srv/clients/acme")));
+        // A rule for everything below a directory also covers the directory itself
+        assert!(matches_path(
+            "/srv/clients/acme/**",
+            Path::new("/srv/clients/acme")
+        ));
+        assert!(matches_path(
+            "/srv/clients/acme/",
+            Path::new("/srv/clients/acme")
+        ));
+        assert!(!matches_path(
+            "/srv/clients/acme/*",
+            Path::new("/srv/clients/acme")
+        ));
+        assert!(!matches_path(
+            "/srv/clients/acme/**",
+            Path::new("/srv/clients/other")
+        ));
+    }
+
+    #[test]
+    fn test_path_specificity() {
+        assert!(path_specificity("/srv/clients/acme/**") > path_specificity("/srv/**"));
+    }
+}
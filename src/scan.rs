@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Find every git repository under `root`, including `root` itself, sorted by path
+///
+/// A directory is a repository when it holds a `.git` directory or file (linked
+/// worktrees and submodules use a file), or when it is a bare repository.
+/// Repositories are searched for nested repositories such as submodules; bare
+/// repositories and symlinks are not descended into.
+pub fn find_repositories(root: &Path) -> Vec<PathBuf> {
+    let mut repositories = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        // Unreadable directories are skipped, like `find` would report and move on
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        let mut subdirs = Vec::new();
+        let mut has_git = false;
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if entry.file_name() == ".git" {
+                has_git = true;
+            } else if file_type.is_dir() {
+                subdirs.push(entry.path());
+            }
+        }
+
+        if has_git {
+            repositories.push(dir);
+        } else if is_bare_repository(&dir) {
+            repositories.push(dir);
+            continue;
+        }
+
+        pending.extend(subdirs);
+    }
+
+    repositories.sort();
+    repositories
+}
+
+/// Check for the layout of a bare repository: `HEAD`, `objects/` and `refs/`
+fn is_bare_repository(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+/// Run `f` on every item with up to `jobs` threads, returning results in input order
+pub fn parallel_map<T, R>(items: &[T], jobs: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_find_repositories() {
+        let temp = TempDir::new("scan");
+        let root = temp.path();
+        let layout = [
+            "api/.git/objects",
+            "api/vendor/lib/.git",
+            "clients/acme/web/.git",
+            "mirror.git/objects",
+            "mirror.git/refs",
+            "notes/drafts",
+        ];
+        for dir in layout {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("mirror.git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        // Linked worktrees and submodules have a `.git` file
+        fs::create_dir_all(root.join("api/worktree")).unwrap();
+        fs::write(
+            root.join("api/worktree/.git"),
+            "gitdir: ../.git/worktrees/x\n",
+        )
+        .unwrap();
+
+        let repositories = find_repositories(root);
+
+        let relative: Vec<_> = repositories
+            .iter()
+            .map(|repo| repo.strip_prefix(root).unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            relative,
+            vec![
+                "api",
+                "api/vendor/lib",
+                "api/worktree",
+                "clients/acme/web",
+                "mirror.git"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parallel_map_keeps_order() {
+        let items: Vec<usize> = (0..50).collect();
+        let squares = parallel_map(&items, 4, |n| n * n);
+        assert_eq!(squares, items.iter().map(|n| n * n).collect::<Vec<_>>());
+        assert!(parallel_map(&Vec::<usize>::new(), 4, |n| *n).is_empty());
+    }
+}
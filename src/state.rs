@@ -0,0 +1,305 @@
+use crate::git::RepoLocation;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What figgit applied to a repository, stored in its git directory so that
+/// `figgit unuse` can undo it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RepoState {
+    /// Workspace applied last
+    pub workspace: String,
+    /// Local values of each key before figgit first set it, empty when it was unset
+    #[serde(default)]
+    pub previous: BTreeMap<String, Vec<String>>,
+}
+
+impl RepoState {
+    /// Get the path to the state file of a repository
+    ///
+    /// The local config is shared by every worktree, so the state lives in the
+    /// common git directory as well.
+    pub fn state_path(repo: &RepoLocation) -> PathBuf {
+        repo.common_dir.join("figgit.toml")
+    }
+
+    /// Load the state of a repository, if figgit applied a workspace to it
+    pub fn load(repo: &RepoLocation) -> Result<Option<Self>> {
+        let state_path = Self::state_path(repo);
+
+        if !state_path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&state_path).context("Failed to read state file")?;
+
+        let state: RepoState = toml::from_str(&content).context("Failed to parse state file")?;
+
+        Ok(Some(state))
+    }
+
+    /// Save the state of a repository
+    pub fn save(&self, repo: &RepoLocation) -> Result<()> {
+        let content = toml::to_string_pretty(self).context("Failed to serialize state")?;
+
+        fs::write(Self::state_path(repo), content).context("Failed to write state file")?;
+
+        Ok(())
+    }
+
+    /// Remove the state file of a repository
+    pub fn remove(repo: &RepoLocation) -> Result<()> {
+        fs::remove_file(Self::state_path(repo)).context("Failed to remove state file")
+    }
+
+    /// Record that a workspace was applied, given the values it replaced
+    ///
+    /// Keys already recorded keep their value, which is the one from before
+    /// figgit first touched them.
+    pub fn record(&mut self, workspace: &str, replaced: Vec<(String, Vec<String>)>) {
+        self.workspace = workspace.to_string();
+        for (key, values) in replaced {
+            self.previous.entry(key).or_insert(values);
+        }
+    }
+
+    /// Get the recorded keys that `settings` do not set, with their values from
+    /// before figgit, i.e. the keys an earlier application left behind
+    pub fn stale_keys<'a>(
+        &'a self,
+        settings: &'a [(String, String)],
+    ) -> impl Iterator<Item = (&'a String, &'a Vec<String>)> {
+        // Git config section and variable names are case-insensitive
+        self.previous.iter().filter(|(key, _)| {
+            !settings
+                .iter()
+                .any(|(setting, _)| setting.eq_ignore_ascii_case(key))
+        })
+    }
+}
+
+/// Repositories figgit applied a workspace to, kept in its state directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Registry {
+    /// Entries keyed by repository root
+    #[serde(default)]
+    pub repositories: BTreeMap<String, RegistryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RegistryEntry {
+    pub workspace: String,
+    /// RFC 3339 UTC time of the last application
+    pub applied_at: String,
+}
+
+impl Registry {
+    /// Get the path to the registry file
+    pub fn registry_path() -> Result<PathBuf> {
+        let state_dir = dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .context("Unable to determine the state directory")?;
+        Ok(state_dir.join("figgit").join("repositories.toml"))
+    }
+
+    /// Load the registry from the registry file
+    pub fn load() -> Result<Self> {
+        let registry_path = Self::registry_path()?;
+
+        if !registry_path.exists() {
+            return Ok(Registry::default());
+        }
+
+        let content = fs::read_to_string(&registry_path).context("Failed to read registry file")?;
+
+        let registry: Registry =
+            toml::from_str(&content).context("Failed to parse registry file")?;
+
+        Ok(registry)
+    }
+
+    /// Save the registry to the registry file
+    pub fn save(&self) -> Result<()> {
+        let registry_path = Self::registry_path()?;
+
+        // Create parent directories if they don't exist
+        if let Some(parent) = registry_path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+
+        let content = toml::to_string_pretty(self).context("Failed to serialize registry")?;
+
+        fs::write(&registry_path, content).context("Failed to write registry file")?;
+
+        Ok(())
+    }
+
+    /// Record that a workspace was applied to a repository
+    pub fn record(&mut self, repo: &Path, workspace: &str, applied_at: SystemTime) {
+        self.repositories.insert(
+            repo.display().to_string(),
+            RegistryEntry {
+                workspace: workspace.to_string(),
+                applied_at: format_timestamp(applied_at),
+            },
+        );
+    }
+
+    /// Forget a repository, returning whether it was registered
+    pub fn remove(&mut self, repo: &Path) -> bool {
+        self.repositories
+            .remove(&repo.display().to_string())
+            .is_some()
+    }
+
+    /// Get the repositories a workspace is applied to, sorted by path
+    pub fn repositories_of(&self, workspace: &str) -> Vec<(&String, &RegistryEntry)> {
+        self.repositories
+            .iter()
+            .filter(|(_, entry)| entry.workspace == workspace)
+            .collect()
+    }
+}
+
+/// Format a time as an RFC 3339 UTC timestamp, e.g. `2024-05-01T09:30:00Z`
+fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil date from the number of days since 1970-01-01, in the proleptic
+    // Gregorian calendar (Howard Hinnant's `civil_from_days`)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_original_values() {
+        let mut state = RepoState::default();
+        state.record(
+            "work",
+            vec![
+                ("user.name".to_string(), vec!["John".to_string()]),
+                ("user.email".to_string(), Vec::new()),
+            ],
+        );
+        state.record(
+            "personal",
+            vec![
+                ("user.name".to_string(), vec!["Work Name".to_string()]),
+                ("pull.rebase".to_string(), vec!["false".to_string()]),
+            ],
+        );
+
+        assert_eq!(state.workspace, "personal");
+        assert_eq!(state.previous["user.name"], vec!["John"]);
+        assert!(state.previous["user.email"].is_empty());
+        assert_eq!(state.previous["pull.rebase"], vec!["false"]);
+    }
+
+    #[test]
+    fn test_stale_keys() {
+        let mut state = RepoState::default();
+        state.record(
+            "work",
+            vec![
+                ("user.name".to_string(), vec!["John".to_string()]),
+                ("core.sshCommand".to_string(), Vec::new()),
+                ("pull.rebase".to_string(), vec!["false".to_string()]),
+            ],
+        );
+
+        let settings = vec![
+            ("user.name".to_string(), "John".to_string()),
+            ("core.sshcommand".to_string(), "ssh -i key".to_string()),
+        ];
+        let stale: Vec<_> = state.stale_keys(&settings).collect();
+        assert_eq!(
+            stale,
+            vec![(&"pull.rebase".to_string(), &vec!["false".to_string()])]
+        );
+        assert_eq!(state.stale_keys(&[]).count(), 3);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut state = RepoState::default();
+        state.record(
+            "work",
+            vec![
+                ("user.name".to_string(), vec!["John".to_string()]),
+                ("user.email".to_string(), Vec::new()),
+            ],
+        );
+
+        let content = toml::to_string_pretty(&state).unwrap();
+        let reloaded: RepoState = toml::from_str(&content).unwrap();
+        assert_eq!(reloaded, state);
+    }
+
+    #[test]
+    fn test_registry_record_and_remove() {
+        let mut registry = Registry::default();
+        let applied_at = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        registry.record(Path::new("/src/api"), "work", applied_at);
+        registry.record(Path::new("/src/blog"), "personal", applied_at);
+        registry.record(Path::new("/src/web"), "work", applied_at);
+        // Applying another workspace replaces the entry
+        registry.record(Path::new("/src/web"), "personal", applied_at);
+
+        let work: Vec<_> = registry
+            .repositories_of("work")
+            .into_iter()
+            .map(|(path, _)| path.as_str())
+            .collect();
+        assert_eq!(work, vec!["/src/api"]);
+        assert_eq!(registry.repositories_of("personal").len(), 2);
+        assert_eq!(
+            registry.repositories["/src/api"].applied_at,
+            "2023-11-14T22:13:20Z"
+        );
+
+        assert!(registry.remove(Path::new("/src/api")));
+        assert!(!registry.remove(Path::new("/src/api")));
+        assert!(registry.repositories_of("work").is_empty());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        let at = |secs| UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        assert_eq!(format_timestamp(at(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(at(1_735_689_599)), "2024-12-31T23:59:59Z");
+    }
+}
@@ -0,0 +1,68 @@
+use crate::git::{self, RepoLocation};
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A directory under the system temporary directory, removed when dropped so
+/// that failing tests clean up too
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty directory, unique to the test `name` and this process
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("figgit-{}-{}", name, std::process::id()));
+        // Left over by a run that was killed before it could clean up
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A repository created with `git init` in a [`TempDir`]
+///
+/// It dereferences to its [`RepoLocation`] so it can be passed to the `_in` helpers.
+pub struct TempRepo {
+    repo: RepoLocation,
+    // Only held so that the repository is removed along with it
+    _dir: TempDir,
+}
+
+impl TempRepo {
+    pub fn new(name: &str) -> Self {
+        let dir = TempDir::new(name);
+        git_init(&[dir.path().to_str().unwrap()]);
+        let repo = git::discover_repo_at(dir.path()).unwrap();
+        TempRepo { repo, _dir: dir }
+    }
+}
+
+impl Deref for TempRepo {
+    type Target = RepoLocation;
+
+    fn deref(&self) -> &RepoLocation {
+        &self.repo
+    }
+}
+
+/// Run `git init --quiet` with extra arguments, such as `--bare` and a path
+pub fn git_init(args: &[&str]) {
+    let status = Command::new("git")
+        .args(["init", "--quiet"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}